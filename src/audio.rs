@@ -0,0 +1,300 @@
+use std::time::Duration;
+
+use bevy::{
+    prelude::*,
+    audio::{AddAudioSource, AudioPlugin, Source},
+};
+
+use crate::{
+    breakout::{
+        BallHitBlock, BallHitPaddle, BallHitWall, BlockDestroyed, HealthChanged, LivesChanged,
+        SeededRng,
+    },
+    GameState, Volume, MAX_VOLUME,
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+// Keeps the waves from clipping when a few sounds play at once
+const AMPLITUDE: f32 = 0.25;
+// Parts of every note spent fading in and out, so notes start and stop without clicking
+const ATTACK: f32 = 0.05;
+const RELEASE: f32 = 0.3;
+// The music sits under the sound effects
+const MUSIC_VOLUME: f32 = 0.3;
+
+// This plugin plays a sound for everything the ball bounces off, for breaking blocks, losing
+// health or a life and for the game ending, with some music looping underneath. Everything is
+// synthesized, so there are no sound files to load, and it all follows the `Volume` setting.
+//
+// It has to be added after bevy's `AudioPlugin`. Apps without one, like headless ones, just
+// stay quiet, and so does a machine without an audio device.
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<AudioPlugin>() {
+            return;
+        }
+
+        app
+            .add_audio_source::<Synth>()
+            .init_resource::<Sounds>()
+            .add_systems(Startup, start_music)
+            .add_systems(
+                Update,
+                (
+                    apply_volume.run_if(resource_changed::<Volume>()),
+                    play_collision_sounds,
+                    play_block_destroyed_sounds,
+                    play_life_lost_sounds,
+                ),
+            )
+            .add_systems(OnEnter(GameState::GameOver), play_game_over_sound);
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Wave {
+    Sine,
+    Square,
+    Triangle,
+    Noise,
+}
+
+impl Wave {
+    // The wave at `phase`, which goes from 0.0 to 1.0 over one period
+    fn sample(&self, phase: f32, rng: &mut SeededRng) -> f32 {
+        match self {
+            Wave::Sine => (phase * std::f32::consts::TAU).sin(),
+            Wave::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Wave::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+            Wave::Noise => rng.next_f32() * 2.0 - 1.0,
+        }
+    }
+}
+
+// A tone that slides from one frequency to another
+#[derive(Clone, Copy, Debug)]
+struct Note {
+    from: f32,
+    to: f32,
+    seconds: f32,
+}
+
+impl Note {
+    fn new(frequency: f32, seconds: f32) -> Note {
+        Note::slide(frequency, frequency, seconds)
+    }
+
+    fn slide(from: f32, to: f32, seconds: f32) -> Note {
+        Note { from, to, seconds }
+    }
+}
+
+// A sound made of notes played one after the other
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct Synth {
+    wave: Wave,
+    notes: Vec<Note>,
+}
+
+pub struct SynthDecoder {
+    synth: Synth,
+    note: usize,
+    sample: u32,
+    phase: f32,
+    rng: SeededRng,
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        loop {
+            let note = self.synth.notes.get(self.note)?;
+            let length = (note.seconds * SAMPLE_RATE as f32) as u32;
+            if self.sample >= length {
+                self.note += 1;
+                self.sample = 0;
+                continue;
+            }
+
+            let progress = self.sample as f32 / length as f32;
+            let frequency = note.from + (note.to - note.from) * progress;
+            // Wrap the phase so it doesn't lose precision over long sounds
+            self.phase = (self.phase + frequency / SAMPLE_RATE as f32) % 1.0;
+            self.sample += 1;
+
+            let envelope = (progress / ATTACK).min(1.0) * ((1.0 - progress) / RELEASE).min(1.0);
+            return Some(self.synth.wave.sample(self.phase, &mut self.rng) * envelope * AMPLITUDE);
+        }
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        let seconds = self.synth.notes.iter().map(|note| note.seconds).sum();
+        Some(Duration::from_secs_f32(seconds))
+    }
+}
+
+impl Decodable for Synth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> SynthDecoder {
+        SynthDecoder {
+            synth: self.clone(),
+            note: 0,
+            sample: 0,
+            phase: 0.0,
+            rng: SeededRng::new(0),
+        }
+    }
+}
+
+// Every sound the game makes
+#[derive(Resource)]
+struct Sounds {
+    paddle: Handle<Synth>,
+    wall: Handle<Synth>,
+    block: Handle<Synth>,
+    block_destroyed: Handle<Synth>,
+    life_lost: Handle<Synth>,
+    game_over: Handle<Synth>,
+    music: Handle<Synth>,
+}
+
+impl FromWorld for Sounds {
+    fn from_world(world: &mut World) -> Self {
+        let mut synths = world.resource_mut::<Assets<Synth>>();
+        let mut add = |wave, notes| synths.add(Synth { wave, notes });
+
+        Sounds {
+            paddle: add(Wave::Square, vec![Note::slide(440.0, 520.0, 0.08)]),
+            wall: add(Wave::Triangle, vec![Note::new(220.0, 0.06)]),
+            block: add(Wave::Square, vec![Note::slide(660.0, 880.0, 0.07)]),
+            block_destroyed: add(Wave::Noise, vec![Note::new(0.0, 0.15)]),
+            life_lost: add(Wave::Square, vec![Note::slide(440.0, 110.0, 0.5)]),
+            game_over: add(
+                Wave::Triangle,
+                [392.0, 330.0, 262.0, 196.0]
+                    .into_iter()
+                    .map(|frequency| Note::new(frequency, 0.3))
+                    .collect(),
+            ),
+            // Arpeggios over C, A minor, F and G
+            music: add(
+                Wave::Sine,
+                [
+                    [131.0, 165.0, 196.0, 165.0],
+                    [110.0, 131.0, 165.0, 131.0],
+                    [87.0, 110.0, 131.0, 110.0],
+                    [98.0, 123.0, 147.0, 123.0],
+                ]
+                    .into_iter()
+                    .flat_map(|chord| chord.into_iter().cycle().take(8))
+                    .map(|frequency| Note::new(frequency, 0.2))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Tag component used to find the music
+#[derive(Component)]
+struct Music;
+
+fn play(commands: &mut Commands, sound: &Handle<Synth>) {
+    commands.spawn(AudioSourceBundle {
+        source: sound.clone(),
+        settings: PlaybackSettings::DESPAWN,
+    });
+}
+
+fn start_music(mut commands: Commands, sounds: Res<Sounds>) {
+    commands.spawn((
+        AudioSourceBundle {
+            source: sounds.music.clone(),
+            settings: PlaybackSettings::LOOP
+                .with_volume(bevy::audio::Volume::new_relative(MUSIC_VOLUME)),
+        },
+        Music,
+        Name::new("Music"),
+    ));
+}
+
+// New sounds pick up the global volume when they start, but the music is already playing and
+// has to be turned up or down by hand
+fn apply_volume(
+    volume: Res<Volume>,
+    mut global_volume: ResMut<GlobalVolume>,
+    music_query: Query<&AudioSink, With<Music>>,
+) {
+    let level = volume.0 as f32 / MAX_VOLUME as f32;
+    *global_volume = GlobalVolume::new(level);
+
+    for sink in &music_query {
+        sink.set_volume(MUSIC_VOLUME * level);
+    }
+}
+
+// A ball can bounce off the same kind of thing more than once in a frame, but it only needs to
+// be heard once
+fn play_collision_sounds(
+    mut commands: Commands,
+    sounds: Res<Sounds>,
+    mut paddle_hits: EventReader<BallHitPaddle>,
+    mut wall_hits: EventReader<BallHitWall>,
+    mut block_hits: EventReader<BallHitBlock>,
+) {
+    if paddle_hits.read().count() > 0 {
+        play(&mut commands, &sounds.paddle);
+    }
+    if wall_hits.read().count() > 0 {
+        play(&mut commands, &sounds.wall);
+    }
+    if block_hits.read().count() > 0 {
+        play(&mut commands, &sounds.block);
+    }
+}
+
+// An explosion can break a lot of blocks at once, which still only gets one sound
+fn play_block_destroyed_sounds(
+    mut commands: Commands,
+    sounds: Res<Sounds>,
+    mut destroyed_blocks: EventReader<BlockDestroyed>,
+) {
+    if destroyed_blocks.read().count() > 0 {
+        play(&mut commands, &sounds.block_destroyed);
+    }
+}
+
+fn play_life_lost_sounds(
+    mut commands: Commands,
+    sounds: Res<Sounds>,
+    mut health_changes: EventReader<HealthChanged>,
+    mut lives_changes: EventReader<LivesChanged>,
+) {
+    let health_lost = health_changes.read().any(HealthChanged::is_loss);
+    let life_lost = lives_changes.read().any(LivesChanged::is_loss);
+    if health_lost || life_lost {
+        play(&mut commands, &sounds.life_lost);
+    }
+}
+
+fn play_game_over_sound(mut commands: Commands, sounds: Res<Sounds>) {
+    play(&mut commands, &sounds.game_over);
+}
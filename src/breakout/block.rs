@@ -0,0 +1,133 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde::Deserialize;
+
+use super::events::BlockDestroyed;
+use super::sim::{BlockColor, Scoreboard};
+
+// Blocks whose centers are this close to an exploding block are destroyed with it. This is just
+// enough to reach the eight blocks around it in a full grid.
+const EXPLOSION_RADIUS: f32 = 120.0;
+// Points for an explosive block, on top of the points for everything it takes with it
+const EXPLOSIVE_SCORE: usize = 2;
+
+// The different kinds of blocks a level can be made of
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Reflect)]
+pub enum BlockKind {
+    // Breaks after being hit `hits` times
+    Normal { hits: u32 },
+    // Can't be broken and doesn't have to be for the level to be cleared
+    Steel,
+    // Breaks in one hit and destroys the blocks around it
+    Explosive,
+}
+
+impl Default for BlockKind {
+    fn default() -> Self {
+        BlockKind::Normal { hits: 1 }
+    }
+}
+
+impl BlockKind {
+    // How many hits a new block of this kind takes before it breaks
+    pub fn hit_points(&self) -> u32 {
+        match self {
+            BlockKind::Normal { hits } => (*hits).max(1),
+            BlockKind::Steel | BlockKind::Explosive => 1,
+        }
+    }
+
+    // Points for breaking a block of this kind. Tougher blocks are worth more.
+    pub fn score(&self) -> usize {
+        match self {
+            BlockKind::Normal { .. } => self.hit_points() as usize,
+            BlockKind::Steel => 0,
+            BlockKind::Explosive => EXPLOSIVE_SCORE,
+        }
+    }
+
+    // Whether this block has to be broken to clear the level
+    pub fn is_breakable(&self) -> bool {
+        *self != BlockKind::Steel
+    }
+}
+
+#[derive(Component, Debug, Reflect)]
+pub struct Block {
+    pub kind: BlockKind,
+    // Hits left before the block breaks
+    pub hit_points: u32,
+}
+
+impl Block {
+    pub fn new(kind: BlockKind) -> Block {
+        Block {
+            kind,
+            hit_points: kind.hit_points(),
+        }
+    }
+}
+
+// Sent when the ball hits a block, `damage_blocks` decides what happens to it
+#[derive(Event)]
+pub struct BlockHit(pub Entity);
+
+// Take a hit point off every block the ball hit this tick, and break the ones that run out.
+// Explosions can set off other explosive blocks, so we keep going until nothing else blows up.
+pub(super) fn damage_blocks(
+    mut commands: Commands,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut block_hits: EventReader<BlockHit>,
+    mut destroyed_events: EventWriter<BlockDestroyed>,
+    mut block_query: Query<(Entity, &Transform, &mut Block, &BlockColor)>,
+) {
+    let mut destroyed: HashSet<Entity> = HashSet::new();
+    let mut explosions: Vec<Vec3> = Vec::new();
+
+    for BlockHit(entity) in block_hits.read() {
+        let Ok((entity, transform, mut block, _)) = block_query.get_mut(*entity) else {
+            continue;
+        };
+        if !block.kind.is_breakable() || destroyed.contains(&entity) {
+            continue;
+        }
+
+        block.hit_points = block.hit_points.saturating_sub(1);
+        if block.hit_points == 0 {
+            destroyed.insert(entity);
+            if block.kind == BlockKind::Explosive {
+                explosions.push(transform.translation);
+            }
+        }
+    }
+
+    while let Some(center) = explosions.pop() {
+        for (entity, transform, block, _) in &block_query {
+            if !block.kind.is_breakable()
+                || destroyed.contains(&entity)
+                || transform.translation.truncate().distance(center.truncate()) > EXPLOSION_RADIUS
+            {
+                continue;
+            }
+
+            destroyed.insert(entity);
+            if block.kind == BlockKind::Explosive {
+                explosions.push(transform.translation);
+            }
+        }
+    }
+
+    for entity in destroyed {
+        if let Ok((_, transform, block, color)) = block_query.get(entity) {
+            let score = block.kind.score();
+            scoreboard.score += score;
+            destroyed_events.send(BlockDestroyed {
+                entity,
+                kind: block.kind,
+                position: transform.translation,
+                score,
+                color: **color,
+            });
+        }
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+
+// Where a moving circle first touches a box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepHit {
+    // How far along the motion the hit happens, from 0.0 (right away) to 1.0 (at the very end)
+    pub time: f32,
+    // Points out of the box at the point of contact
+    pub normal: Vec2,
+}
+
+// Sweep a circle of `radius` from `start` along `motion` and find the first time it touches the
+// box at `box_center`. This is the same as casting a ray from the circle's center at the box
+// grown by the radius, whose corners are rounded off into quarter circles. Only hits that move
+// the circle further into the box count, so a circle that is already leaving is let go.
+pub fn sweep_circle_aabb(
+    start: Vec2,
+    motion: Vec2,
+    radius: f32,
+    box_center: Vec2,
+    box_half_size: Vec2,
+) -> Option<SweepHit> {
+    let local = start - box_center;
+    let grown = box_half_size + Vec2::splat(radius);
+
+    // Already overlapping, push out the shortest way
+    let closest = local.clamp(-box_half_size, box_half_size);
+    let offset = local - closest;
+    if offset.length_squared() < radius * radius {
+        let normal = if offset != Vec2::ZERO {
+            offset.normalize()
+        } else if grown.x - local.x.abs() < grown.y - local.y.abs() {
+            Vec2::new(local.x.signum(), 0.0)
+        } else {
+            Vec2::new(0.0, local.y.signum())
+        };
+
+        return (motion.dot(normal) < 0.0).then_some(SweepHit { time: 0.0, normal });
+    }
+
+    // Cast against the grown box, one axis (slab) at a time
+    let mut enter = 0.0_f32;
+    let mut exit = 1.0_f32;
+    let mut normal = Vec2::ZERO;
+
+    for axis in 0..2 {
+        let position = local[axis];
+        let speed = motion[axis];
+
+        if speed == 0.0 {
+            // Moving parallel to this slab, so we're either always in it or never
+            if position.abs() > grown[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let near = (-grown[axis] * speed.signum() - position) / speed;
+        let far = (grown[axis] * speed.signum() - position) / speed;
+
+        if near > enter {
+            enter = near;
+            normal = Vec2::ZERO;
+            normal[axis] = -speed.signum();
+        }
+        exit = exit.min(far);
+
+        if enter > exit {
+            return None;
+        }
+    }
+
+    // If we came in through a corner of the grown box, we have to hit the rounded corner itself
+    let contact = local + motion * enter;
+    if contact.x.abs() > box_half_size.x && contact.y.abs() > box_half_size.y {
+        let corner = Vec2::new(
+            box_half_size.x * contact.x.signum(),
+            box_half_size.y * contact.y.signum(),
+        );
+        return sweep_circle_point(local, motion, radius, corner);
+    }
+
+    if normal == Vec2::ZERO {
+        return None;
+    }
+
+    Some(SweepHit { time: enter, normal })
+}
+
+// Sweep a circle at a single point, which is where it hits the corner of a box
+fn sweep_circle_point(start: Vec2, motion: Vec2, radius: f32, point: Vec2) -> Option<SweepHit> {
+    let from_point = start - point;
+    let a = motion.length_squared();
+    let b = from_point.dot(motion);
+    let c = from_point.length_squared() - radius * radius;
+
+    let discriminant = b * b - a * c;
+    if a == 0.0 || discriminant < 0.0 {
+        return None;
+    }
+
+    let time = (-b - discriminant.sqrt()) / a;
+    if !(0.0..=1.0).contains(&time) {
+        return None;
+    }
+
+    Some(SweepHit {
+        time,
+        normal: (from_point + motion * time).normalize(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS: f32 = 15.0;
+    const BLOCK_HALF_SIZE: Vec2 = Vec2::new(50.0, 15.0);
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-4, "{a} is not {b}");
+    }
+
+    #[test]
+    fn fast_ball_does_not_tunnel_through_thin_wall() {
+        // A 10px wall, and a ball that would end up well past it after one tick
+        let wall_center = Vec2::new(450.0, 0.0);
+        let wall_half_size = Vec2::new(5.0, 300.0);
+        let hit = sweep_circle_aabb(
+            Vec2::new(400.0, 0.0),
+            Vec2::new(1000.0, 0.0),
+            RADIUS,
+            wall_center,
+            wall_half_size,
+        )
+        .expect("the ball went through the wall");
+
+        // It touches the wall once its center is a radius away from the wall's left side
+        assert!((hit.time - 0.03).abs() < 1e-5);
+        assert_close(hit.normal, Vec2::NEG_X);
+    }
+
+    #[test]
+    fn edge_hits_point_out_of_the_side_that_was_hit() {
+        let from_below = sweep_circle_aabb(
+            Vec2::new(0.0, -100.0),
+            Vec2::new(0.0, 200.0),
+            RADIUS,
+            Vec2::ZERO,
+            BLOCK_HALF_SIZE,
+        )
+        .unwrap();
+        assert!((from_below.time - 0.35).abs() < 1e-5);
+        assert_close(from_below.normal, Vec2::NEG_Y);
+
+        let from_the_right = sweep_circle_aabb(
+            Vec2::new(100.0, 10.0),
+            Vec2::new(-100.0, -10.0),
+            RADIUS,
+            Vec2::ZERO,
+            BLOCK_HALF_SIZE,
+        )
+        .unwrap();
+        assert!((from_the_right.time - 0.35).abs() < 1e-5);
+        assert_close(from_the_right.normal, Vec2::X);
+    }
+
+    #[test]
+    fn missing_the_box_is_no_hit() {
+        let hit = sweep_circle_aabb(
+            Vec2::new(0.0, -100.0),
+            Vec2::new(200.0, 0.0),
+            RADIUS,
+            Vec2::ZERO,
+            BLOCK_HALF_SIZE,
+        );
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn corners_are_rounded() {
+        let half_size = Vec2::splat(10.0);
+
+        // Straight at the corner, so it bounces back the way it came
+        let hit = sweep_circle_aabb(
+            Vec2::new(-40.0, -40.0),
+            Vec2::new(40.0, 40.0),
+            RADIUS,
+            Vec2::ZERO,
+            half_size,
+        )
+        .unwrap();
+        let expected_time = (30.0 - RADIUS / 2.0_f32.sqrt()) / 40.0;
+        assert!((hit.time - expected_time).abs() < 1e-4);
+        assert_close(hit.normal, Vec2::new(-1.0, -1.0).normalize());
+
+        // Through the corner of the box grown by the radius, but too far from the real corner
+        // to touch it
+        let hit = sweep_circle_aabb(
+            Vec2::new(-65.0, -20.0),
+            Vec2::new(100.0, 100.0),
+            RADIUS,
+            Vec2::ZERO,
+            half_size,
+        );
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn overlapping_ball_is_pushed_out_only_when_moving_in() {
+        // Just inside the offset a bounce leaves between the ball and the box
+        let start = Vec2::new(0.0, -BLOCK_HALF_SIZE.y - RADIUS + 0.005);
+
+        let moving_in =
+            sweep_circle_aabb(start, Vec2::new(0.0, 10.0), RADIUS, Vec2::ZERO, BLOCK_HALF_SIZE)
+                .unwrap();
+        assert_eq!(moving_in.time, 0.0);
+        assert_close(moving_in.normal, Vec2::NEG_Y);
+
+        let moving_out =
+            sweep_circle_aabb(start, Vec2::new(0.0, -10.0), RADIUS, Vec2::ZERO, BLOCK_HALF_SIZE);
+        assert_eq!(moving_out, None);
+    }
+
+    #[test]
+    fn ball_resting_against_box_leaves_freely() {
+        // Where a bounce leaves the ball, a little way off the surface
+        let start = Vec2::new(0.0, -BLOCK_HALF_SIZE.y - RADIUS - 0.01);
+
+        let moving_away =
+            sweep_circle_aabb(start, Vec2::new(0.0, -10.0), RADIUS, Vec2::ZERO, BLOCK_HALF_SIZE);
+        assert_eq!(moving_away, None);
+
+        let moving_along =
+            sweep_circle_aabb(start, Vec2::new(10.0, 0.0), RADIUS, Vec2::ZERO, BLOCK_HALF_SIZE);
+        assert_eq!(moving_along, None);
+
+        let moving_back =
+            sweep_circle_aabb(start, Vec2::new(0.0, 10.0), RADIUS, Vec2::ZERO, BLOCK_HALF_SIZE)
+                .unwrap();
+        assert!(moving_back.time < 0.01);
+        assert_close(moving_back.normal, Vec2::NEG_Y);
+    }
+}
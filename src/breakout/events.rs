@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+
+use super::block::BlockKind;
+use super::sim::WallLocation;
+
+// Everything worth knowing about that happens in a game. The simulation sends these as it goes,
+// so sounds, particles and anything else can react to the game without touching its rules.
+// They are sent from `FixedUpdate`, so read them in `Update` or later in the `FixedUpdate` chain.
+
+// The ball bounced off the top or side of the paddle
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BallHitPaddle {
+    pub ball: Entity,
+    // Where the ball was when it hit
+    pub position: Vec2,
+    // How fast the ball left the paddle
+    pub speed: f32,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BallHitWall {
+    pub ball: Entity,
+    pub wall: WallLocation,
+    pub position: Vec2,
+}
+
+// The ball hit a block, whether or not it broke. See `BlockDestroyed` for the ones that did.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BallHitBlock {
+    pub ball: Entity,
+    pub block: Entity,
+    pub position: Vec2,
+}
+
+// Sent for every block that breaks, whether the ball hit it or something blew it up. The block
+// entity is already on its way out when this is read.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BlockDestroyed {
+    pub entity: Entity,
+    pub kind: BlockKind,
+    pub position: Vec3,
+    // Points the block was worth
+    pub score: usize,
+    pub color: Color,
+}
+
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HealthChanged {
+    pub previous: usize,
+    pub current: usize,
+}
+
+impl HealthChanged {
+    pub fn is_loss(&self) -> bool {
+        self.current < self.previous
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LivesChanged {
+    pub previous: usize,
+    pub current: usize,
+}
+
+impl LivesChanged {
+    pub fn is_loss(&self) -> bool {
+        self.current < self.previous
+    }
+}
+
+// Every breakable block on the level is gone
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelCleared {
+    pub level: usize,
+    pub score: usize,
+}
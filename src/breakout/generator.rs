@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+
+use super::block::BlockKind;
+use super::layout::{BlockGrid, BlockSpec, LevelLayout};
+use super::rng::SeededRng;
+use super::sim::PADDLE_SIZE;
+
+// Colors the generator picks its block types from
+const PALETTE: [Color; 6] = [
+    Color::PINK,
+    Color::GOLD,
+    Color::TURQUOISE,
+    Color::VIOLET,
+    Color::CRIMSON,
+    Color::SEA_GREEN,
+];
+// Special blocks always look the same so players learn to spot them
+const STEEL_COLOR: Color = Color::SILVER;
+const EXPLOSIVE_COLOR: Color = Color::ORANGE_RED;
+
+// The easiest generated level has this many rows, and each step of difficulty adds one until
+// the grid is full
+const MIN_ROWS: usize = 3;
+// Generated levels never leave more than this share of their cells empty
+const MAX_HOLE_CHANCE: f32 = 0.3;
+// The paddle gets a little narrower with each step of difficulty, down to this width
+const MIN_PADDLE_WIDTH: f32 = 80.0;
+const PADDLE_SHRINK_PER_DIFFICULTY: f32 = 5.0;
+// Each step of difficulty makes more blocks take extra hits, up to this share of them
+const TOUGH_CHANCE_PER_DIFFICULTY: f32 = 0.1;
+const MAX_TOUGH_CHANCE: f32 = 0.5;
+const MAX_TOUGH_HITS: u32 = 3;
+// Explosive and steel blocks only show up from these difficulties on
+const EXPLOSIVE_DIFFICULTY: u32 = 2;
+const EXPLOSIVE_CHANCE: f32 = 0.05;
+const STEEL_DIFFICULTY: u32 = 3;
+const STEEL_CHANCE: f32 = 0.08;
+
+// The overall shape of a generated level
+#[derive(Clone, Copy, Debug)]
+enum Pattern {
+    Solid,
+    Checkers,
+    Stripes,
+    Pillars,
+    Diamond,
+}
+
+const PATTERNS: [Pattern; 5] = [
+    Pattern::Solid,
+    Pattern::Checkers,
+    Pattern::Stripes,
+    Pattern::Pillars,
+    Pattern::Diamond,
+];
+
+impl Pattern {
+    // Whether the cell at `row` and `column` is part of the pattern. Columns are counted from the
+    // outside in, so the same answer is used for both halves of the level.
+    fn contains(&self, row: usize, column: usize, n_rows: usize, half_columns: usize) -> bool {
+        match self {
+            Pattern::Solid => true,
+            Pattern::Checkers => (row + column).is_multiple_of(2),
+            Pattern::Stripes => row.is_multiple_of(2),
+            Pattern::Pillars => column.is_multiple_of(2),
+            Pattern::Diamond => {
+                let from_middle_row = row.abs_diff(n_rows / 2);
+                let from_middle_column = half_columns - 1 - column;
+                from_middle_row + from_middle_column <= n_rows.max(half_columns) / 2 + 1
+            }
+        }
+    }
+}
+
+// How the block types are spread over the level
+#[derive(Clone, Copy, Debug)]
+enum Coloring {
+    ByRow,
+    ByColumn,
+    Scattered,
+}
+
+const COLORINGS: [Coloring; 3] = [Coloring::ByRow, Coloring::ByColumn, Coloring::Scattered];
+
+// Turn the seed of a game and a level number into the seed for that level, so every level of a
+// game is different but the whole game can be replayed from one number. The game seed is
+// scrambled before the level is mixed in, so neighbouring games don't share levels one apart.
+pub fn level_seed(game_seed: u64, level: usize) -> u64 {
+    let game = SeededRng::new(game_seed).next_u64();
+    SeededRng::new(game ^ level as u64).next_u64()
+}
+
+// Build a random level from a seed. The same seed and difficulty always give exactly the same
+// layout. Levels are mirrored left to right, always fit inside the walls and always leave the
+// gap above the paddle free. Higher difficulties add rows, block colors, tougher, explosive and
+// steel blocks, and a narrower paddle.
+pub fn generate_level(seed: u64, difficulty: u32) -> LevelLayout {
+    let mut rng = SeededRng::new(seed);
+
+    let n_columns = BlockGrid::max_columns();
+    let half_columns = n_columns.div_ceil(2);
+    let n_rows = (MIN_ROWS + difficulty as usize).min(BlockGrid::max_rows());
+    let n_types = (1 + difficulty as usize / 2).min(PALETTE.len());
+
+    let pattern = PATTERNS[rng.below(PATTERNS.len())];
+    let coloring = COLORINGS[rng.below(COLORINGS.len())];
+    let hole_chance = rng.next_f32() * MAX_HOLE_CHANCE;
+    let first_type = rng.below(PALETTE.len());
+    let tough_chance = (TOUGH_CHANCE_PER_DIFFICULTY * difficulty as f32).min(MAX_TOUGH_CHANCE);
+    let explosive_chance = if difficulty >= EXPLOSIVE_DIFFICULTY { EXPLOSIVE_CHANCE } else { 0.0 };
+    let steel_chance = if difficulty >= STEEL_DIFFICULTY { STEEL_CHANCE } else { 0.0 };
+
+    // Decide on the left half (including the middle column when there is an odd number), then
+    // mirror it onto the right half
+    let mut cells = vec![vec![None; half_columns]; n_rows];
+    for (row, row_cells) in cells.iter_mut().enumerate() {
+        for (column, cell) in row_cells.iter_mut().enumerate() {
+            // Always draw the random numbers so the layout only depends on the seed
+            let is_hole = rng.chance(hole_chance);
+            let scattered_type = rng.below(n_types);
+            let kind_roll = rng.next_f32();
+            let extra_hits = rng.below(MAX_TOUGH_HITS as usize - 1) as u32;
+
+            if !pattern.contains(row, column, n_rows, half_columns) || is_hole {
+                continue;
+            }
+
+            let block_type = match coloring {
+                Coloring::ByRow => row % n_types,
+                Coloring::ByColumn => column % n_types,
+                Coloring::Scattered => scattered_type,
+            };
+            let color = PALETTE[(first_type + block_type) % PALETTE.len()];
+
+            *cell = Some(if kind_roll < steel_chance {
+                (STEEL_COLOR, BlockKind::Steel)
+            } else if kind_roll < steel_chance + explosive_chance {
+                (EXPLOSIVE_COLOR, BlockKind::Explosive)
+            } else if kind_roll < steel_chance + explosive_chance + tough_chance {
+                (color, BlockKind::Normal { hits: 2 + extra_hits })
+            } else {
+                (color, BlockKind::default())
+            });
+        }
+    }
+
+    // A level has to have something to break, so fall back to a full bottom row
+    if cells.iter().flatten().flatten().all(|(_, kind)| !kind.is_breakable()) {
+        cells[0] = vec![Some((PALETTE[first_type], BlockKind::default())); half_columns];
+    }
+
+    let mut blocks = Vec::new();
+    for (row, row_cells) in cells.iter().enumerate() {
+        for column in 0..n_columns {
+            let mirrored_column = column.min(n_columns - 1 - column);
+            if let Some((color, kind)) = row_cells[mirrored_column] {
+                blocks.push(BlockSpec {
+                    position: BlockGrid::cell_position(row, column, n_columns),
+                    color,
+                    kind,
+                });
+            }
+        }
+    }
+
+    let layout = LevelLayout {
+        name: format!("Random {seed}"),
+        blocks,
+        paddle_width: (PADDLE_SIZE.x - PADDLE_SHRINK_PER_DIFFICULTY * difficulty as f32)
+            .max(MIN_PADDLE_WIDTH),
+    };
+    debug_assert!(layout.fits_in_arena());
+
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breakout::sim::BLOCK_SIZE;
+
+    #[test]
+    fn same_seed_gives_same_level() {
+        let layout = generate_level(42, 3);
+        assert_eq!(layout, generate_level(42, 3));
+        assert_eq!(layout.blocks.len(), 20);
+        assert_ne!(layout, generate_level(43, 3));
+    }
+
+    #[test]
+    fn levels_are_mirrored() {
+        for seed in 0..20 {
+            let layout = generate_level(seed, seed as u32 % 8);
+            for block in &layout.blocks {
+                let mirrored = Vec2::new(-block.position.x, block.position.y);
+                assert!(
+                    layout.blocks.iter().any(|other| {
+                        other.position.distance(mirrored) < 0.01
+                            && other.color == block.color
+                            && other.kind == block.kind
+                    }),
+                    "seed {seed}: no mirror for the block at {}",
+                    block.position,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn levels_fit_in_arena() {
+        for seed in 0..50 {
+            for difficulty in 0..12 {
+                assert!(generate_level(seed, difficulty).fits_in_arena());
+            }
+        }
+    }
+
+    #[test]
+    fn gap_above_paddle_stays_empty() {
+        for difficulty in 0..=20 {
+            for seed in 0..10 {
+                let layout = generate_level(seed, difficulty);
+                assert!(!layout.blocks.is_empty());
+                for block in &layout.blocks {
+                    let bottom = block.position.y - BLOCK_SIZE.y / 2.0;
+                    assert!(bottom >= BlockGrid::bottom_edge() - 0.01);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbouring_seeds_share_no_levels() {
+        for game_seed in 0..100 {
+            for level in 1..10 {
+                assert_ne!(level_seed(game_seed, level + 1), level_seed(game_seed + 1, level));
+            }
+        }
+    }
+}
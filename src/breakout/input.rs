@@ -0,0 +1,312 @@
+use bevy::{
+    input::{
+        gamepad::{GamepadConnection, GamepadConnectionEvent},
+        InputSystem,
+    },
+    prelude::*,
+    utils::HashMap,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+use serde::{Deserialize, Serialize};
+
+use super::sim::{PaddleInput, PausedState};
+use crate::{
+    controls::{InputAction, KeyBindings},
+    menu::MenuState,
+    GameState,
+};
+
+// How far the stick has to be pushed before the paddle moves, on top of the gamepad's own
+// dead zone, so a worn stick doesn't make the paddle drift
+const STICK_DEAD_ZONE: f32 = 0.15;
+// A touch this short that doesn't move further than this is a tap, anything else is a drag
+const TAP_SECONDS: f32 = 0.25;
+const TAP_DISTANCE: f32 = 20.0;
+
+// This plugin turns the keyboard, any connected gamepads, the mouse and touches into
+// `PaddleInput` for the simulation and handles pausing. Gamepads can be plugged in and out at
+// any time; unplugging one in the middle of a game pauses it. On a touch screen the paddle
+// follows a finger dragged across the screen, a tap launches the ball and a two-finger tap
+// pauses.
+pub struct BreakoutInputPlugin;
+
+impl Plugin for BreakoutInputPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .register_type::<MouseControl>()
+            .init_resource::<MouseControl>()
+            .init_resource::<KeyBindings>()
+            // Read the input right after bevy has updated it so the fixed update sees this
+            // frame's input
+            .add_systems(
+                PreUpdate,
+                (paddle_input, pointer_paddle_input).chain().after(InputSystem),
+            )
+            .add_systems(
+                Update,
+                (
+                    log_gamepad_connections,
+                    grab_cursor,
+                    pause.run_if(not(in_state(PausedState::Nil))),
+                ),
+            );
+    }
+}
+
+// What the mouse does, picked in Settings > Game. It starts off, so a mouse that happens to be
+// lying next to the keyboard can't pull the paddle away from the player.
+#[derive(
+    Resource, Component, Reflect, Default, Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
+pub enum MouseControl {
+    // Only the menus use the mouse
+    #[default]
+    Off,
+    // The paddle follows the cursor and a click launches the ball
+    Follow,
+    // Like `Follow`, but the cursor is also kept in the window and hidden during play
+    Confined,
+}
+
+// The keyboard and every gamepad can move the paddle at the same time. The sticks move it in
+// proportion to how far they are pushed, the keys and the D-pad at full speed.
+fn paddle_input(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    game_state: Res<State<GameState>>,
+    paused_state: Res<State<PausedState>>,
+    mut paddle_input: ResMut<PaddleInput>,
+) {
+    let mut direction = 0.0;
+    let mut launch = bindings.just_pressed(InputAction::Launch, &keys);
+
+    if bindings.pressed(InputAction::MoveLeft, &keys) {
+        direction -= 1.0;
+    }
+
+    if bindings.pressed(InputAction::MoveRight, &keys) {
+        direction += 1.0;
+    }
+
+    for gamepad in gamepads.iter() {
+        if buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadLeft)) {
+            direction -= 1.0;
+        }
+        if buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadRight)) {
+            direction += 1.0;
+        }
+
+        let stick = axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+            .unwrap_or(0.0);
+        if stick.abs() > STICK_DEAD_ZONE {
+            direction += stick;
+        }
+
+        launch |= buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South));
+    }
+
+    paddle_input.direction = direction.clamp(-1.0, 1.0);
+
+    // The simulation clears this once it has launched the ball. The same buttons pick things in
+    // the menus, which shouldn't launch the ball as soon as the game starts or resumes.
+    if launch && playing(&game_state, &paused_state) {
+        paddle_input.launch = true;
+    }
+}
+
+// The paddle goes wherever the mouse was moved to, or the first finger on the screen is. Using
+// the keys or a gamepad takes over again until the pointer moves.
+fn pointer_paddle_input(
+    mouse_control: Res<MouseControl>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    game_state: Res<State<GameState>>,
+    paused_state: Res<State<PausedState>>,
+    time: Res<Time>,
+    // When each touch on the screen started, to tell taps from drags, and whether another
+    // finger was down at the same time
+    mut touch_starts: Local<HashMap<u64, (f32, bool)>>,
+    mut paddle_input: ResMut<PaddleInput>,
+) {
+    // Keys and gamepads take over from the pointer, and so does turning the mouse off, which
+    // can happen from the pause screen in the middle of a game
+    let mouse_turned_off = mouse_control.is_changed() && *mouse_control == MouseControl::Off;
+    if paddle_input.direction != 0.0 || mouse_turned_off {
+        paddle_input.target_x = None;
+    }
+
+    let mut pointer = None;
+    let mut launch = false;
+
+    if *mouse_control != MouseControl::Off {
+        pointer = cursor_moved.read().last().map(|moved| moved.position);
+        launch |= mouse_buttons.just_pressed(MouseButton::Left);
+    }
+
+    if let Some(touch) = touches.iter().next() {
+        pointer = Some(touch.position());
+    }
+    for touch in touches.iter_just_pressed() {
+        touch_starts.insert(touch.id(), (time.elapsed_seconds(), false));
+    }
+    // Fingers that were part of a two-finger tap don't launch
+    if touches.iter().count() >= 2 {
+        for (_, with_others) in touch_starts.values_mut() {
+            *with_others = true;
+        }
+    }
+    for touch in touches.iter_just_released() {
+        let Some((start, with_others)) = touch_starts.remove(&touch.id()) else {
+            continue;
+        };
+        if !with_others
+            && time.elapsed_seconds() - start < TAP_SECONDS
+            && touch.distance().length() < TAP_DISTANCE
+        {
+            launch = true;
+        }
+    }
+    for touch in touches.iter_just_canceled() {
+        touch_starts.remove(&touch.id());
+    }
+
+    // Pointers are in window coordinates, the paddle lives in world coordinates
+    if let (Some(pointer), Some((camera, camera_transform))) = (pointer, camera_query.iter().next()) {
+        if let Some(position) = camera.viewport_to_world_2d(camera_transform, pointer) {
+            paddle_input.target_x = Some(position.x);
+        }
+    }
+
+    if launch && playing(&game_state, &paused_state) {
+        paddle_input.launch = true;
+    }
+}
+
+// Whether the ball is in play, rather than a menu being up
+fn playing(game_state: &State<GameState>, paused_state: &State<PausedState>) -> bool {
+    *game_state.get() == GameState::InGame && *paused_state.get() == PausedState::Running
+}
+
+// In confined mode the cursor is kept in the window and out of sight while playing, and let go
+// in the menus so the buttons can be clicked. Browsers can't confine the cursor, so there the
+// grab is refused and the cursor only hides.
+fn grab_cursor(
+    mouse_control: Res<MouseControl>,
+    game_state: Res<State<GameState>>,
+    paused_state: Res<State<PausedState>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+
+    let grab = *mouse_control == MouseControl::Confined && playing(&game_state, &paused_state);
+    let grab_mode = if grab { CursorGrabMode::Confined } else { CursorGrabMode::None };
+
+    // Only touch the window when something changes, so it isn't flagged as changed every frame
+    if window.cursor.grab_mode != grab_mode {
+        window.cursor.grab_mode = grab_mode;
+        window.cursor.visible = !grab;
+    }
+}
+
+fn log_gamepad_connections(mut connections: EventReader<GamepadConnectionEvent>) {
+    for connection in connections.read() {
+        match &connection.connection {
+            GamepadConnection::Connected(info) => {
+                info!("Gamepad {} connected: {}", connection.gamepad.id, info.name);
+            }
+            GamepadConnection::Disconnected => {
+                info!("Gamepad {} disconnected", connection.gamepad.id);
+            }
+        }
+    }
+}
+
+fn pause(
+    paused_state: Res<State<PausedState>>,
+    mut next_state: ResMut<NextState<PausedState>>,
+    game_state: Res<State<GameState>>,
+    current_menu_state: Res<State<MenuState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    mut connections: EventReader<GamepadConnectionEvent>,
+) {
+    let toggle = bindings.just_pressed(InputAction::Pause, &keys)
+        || gamepads.iter().any(|gamepad| {
+            buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start))
+        });
+    // A second finger landing on the screen pauses. Resuming is done from the menu.
+    let two_finger_tap = touches.any_just_pressed() && touches.iter().count() >= 2;
+    // A player whose controller dropped out can't do anything about the ball
+    let disconnected = connections.read().any(GamepadConnectionEvent::disconnected);
+
+    match paused_state.get() {
+        // The pause key is also the back key in the menus, so it only resumes from the pause
+        // screen and not from the settings opened from it
+        PausedState::Paused if toggle && *current_menu_state.get() == MenuState::Paused => {
+            menu_state.set(MenuState::Disabled);
+            next_state.set(PausedState::Running);
+        },
+        // The level cleared screen moves on by itself, so only pause during play. The game stays
+        // on screen, frozen under the pause screen.
+        PausedState::Running
+            if (toggle || two_finger_tap || disconnected)
+                && *game_state.get() == GameState::InGame =>
+        {
+            menu_state.set(MenuState::Paused);
+            next_state.set(PausedState::Paused);
+        },
+        _ => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::touch::Touches;
+
+    use super::*;
+
+    fn pointer_world(mouse_control: MouseControl) -> World {
+        let mut world = World::new();
+        world.insert_resource(mouse_control);
+        world.init_resource::<Events<CursorMoved>>();
+        world.init_resource::<Input<MouseButton>>();
+        world.init_resource::<Touches>();
+        world.insert_resource(State::new(GameState::InGame));
+        world.insert_resource(State::new(PausedState::Running));
+        world.init_resource::<Time>();
+        world.insert_resource(PaddleInput { target_x: Some(100.0), ..default() });
+        world
+    }
+
+    #[test]
+    fn turning_mouse_off_lets_go_of_paddle() {
+        let mut world = pointer_world(MouseControl::Follow);
+        let system = world.register_system(pointer_paddle_input);
+
+        // Without the cursor moving, the paddle keeps going where it was last sent
+        world.run_system(system).unwrap();
+        assert_eq!(world.resource::<PaddleInput>().target_x, Some(100.0));
+
+        *world.resource_mut::<MouseControl>() = MouseControl::Off;
+        world.run_system(system).unwrap();
+        assert_eq!(world.resource::<PaddleInput>().target_x, None);
+
+        // Only the change itself lets go, a finger can still take the paddle afterwards
+        world.resource_mut::<PaddleInput>().target_x = Some(50.0);
+        world.run_system(system).unwrap();
+        assert_eq!(world.resource::<PaddleInput>().target_x, Some(50.0));
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use super::block::BlockKind;
+use super::sim::{BLOCK_SIZE, LEFT_WALL, PADDLE_SIZE, PADDLE_Y, RIGHT_WALL, TOP_WALL, WALL_THICKNESS};
+
+// Exact values
+const GAP_BETWEEN_PADDLE_AND_BLOCKS: f32 = 270.0;
+pub const GAP_BETWEEN_BLOCKS: f32 = 5.0;
+// These are lower bounds that are used as we compute the # of blocks
+const GAP_BETWEEN_BLOCKS_AND_CEILING: f32 = 20.0;
+const GAP_BETWEEN_BLOCKS_AND_SIDES: f32 = 20.0;
+
+pub const BLOCK_COLOR: Color = Color::PINK;
+// Leeway for rounding errors when checking that blocks fit
+const FIT_TOLERANCE: f32 = 0.01;
+
+// A single block in a level
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockSpec {
+    pub position: Vec2,
+    pub color: Color,
+    pub kind: BlockKind,
+}
+
+// Everything needed to set up one level: where the blocks are and how wide the paddle is. Levels
+// usually come from asset files (see `level_file`), but a layout can also be built by hand,
+// which is handy when running the simulation headless.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct LevelLayout {
+    pub name: String,
+    pub blocks: Vec<BlockSpec>,
+    pub paddle_width: f32,
+}
+
+impl Default for LevelLayout {
+    fn default() -> Self {
+        LevelLayout::full_grid()
+    }
+}
+
+impl LevelLayout {
+    // The classic layout: as many rows and columns of blocks as will fit
+    pub fn full_grid() -> LevelLayout {
+        let mut blocks = Vec::new();
+
+        for row in 0..BlockGrid::max_rows() {
+            for column in 0..BlockGrid::max_columns() {
+                blocks.push(BlockSpec {
+                    position: BlockGrid::cell_position(row, column, BlockGrid::max_columns()),
+                    color: BLOCK_COLOR,
+                    kind: BlockKind::default(),
+                });
+            }
+        }
+
+        LevelLayout {
+            name: String::from("The Wall"),
+            blocks,
+            paddle_width: PADDLE_SIZE.x,
+        }
+    }
+
+    // Check that every block is between the walls, under the ceiling and leaves the paddle the
+    // room it needs
+    pub fn fits_in_arena(&self) -> bool {
+        let half_block = BLOCK_SIZE / 2.0;
+
+        self.blocks.iter().all(|block| {
+            block.position.x - half_block.x >= LEFT_WALL + WALL_THICKNESS / 2.0 - FIT_TOLERANCE
+                && block.position.x + half_block.x <= RIGHT_WALL - WALL_THICKNESS / 2.0 + FIT_TOLERANCE
+                && block.position.y + half_block.y <= TOP_WALL - WALL_THICKNESS / 2.0 + FIT_TOLERANCE
+                && block.position.y - half_block.y >= BlockGrid::bottom_edge() - FIT_TOLERANCE
+        })
+    }
+}
+
+// The area above the paddle where blocks can be placed, split up into cells the size of a
+// block. Rows are counted from the bottom, so row 0 always leaves the same gap to the paddle.
+pub struct BlockGrid;
+
+impl BlockGrid {
+    fn total_width() -> f32 {
+        (RIGHT_WALL - LEFT_WALL) - 2.0 * GAP_BETWEEN_BLOCKS_AND_SIDES
+    }
+
+    // Lowest a block may go, everything below is kept free for the paddle
+    pub fn bottom_edge() -> f32 {
+        PADDLE_Y + GAP_BETWEEN_PADDLE_AND_BLOCKS
+    }
+
+    fn total_height() -> f32 {
+        TOP_WALL - BlockGrid::bottom_edge() - GAP_BETWEEN_BLOCKS_AND_CEILING
+    }
+
+    // Given our space available, compute number of rows and columns that will fit
+    pub fn max_columns() -> usize {
+        assert!(BlockGrid::total_width() > 0.0);
+        (BlockGrid::total_width() / (BLOCK_SIZE.x + GAP_BETWEEN_BLOCKS)).floor() as usize
+    }
+
+    pub fn max_rows() -> usize {
+        assert!(BlockGrid::total_height() > 0.0);
+        (BlockGrid::total_height() / (BLOCK_SIZE.y + GAP_BETWEEN_BLOCKS)).floor() as usize
+    }
+
+    // Center of the block at `row` (counted from the bottom) and `column` when `n_columns` are
+    // centered between the walls
+    pub fn cell_position(row: usize, column: usize, n_columns: usize) -> Vec2 {
+        let n_vertical_gaps = n_columns.saturating_sub(1);
+
+        // Because we need to round the # of columns, the space on the top and
+        // sides of the blocks is only a lower bound, not an exact value
+        let center_of_blocks = (LEFT_WALL + RIGHT_WALL) / 2.0;
+        let left_edge_of_blocks = center_of_blocks
+            // Space taken up by the blocks
+            - (n_columns as f32 / 2.0 * BLOCK_SIZE.x)
+            // Space taken up by the gaps
+            - (n_vertical_gaps as f32 / 2.0 * GAP_BETWEEN_BLOCKS);
+
+        let offset_x = left_edge_of_blocks + BLOCK_SIZE.x / 2.0;
+        let offset_y = BlockGrid::bottom_edge() + BLOCK_SIZE.y / 2.0;
+
+        Vec2::new(
+            offset_x + column as f32 * (BLOCK_SIZE.x + GAP_BETWEEN_BLOCKS),
+            offset_y + row as f32 * (BLOCK_SIZE.y + GAP_BETWEEN_BLOCKS)
+        )
+    }
+}
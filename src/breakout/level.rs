@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+
+use super::block::Block;
+use super::events::LevelCleared;
+use super::generator::{generate_level, level_seed};
+use super::layout::LevelLayout;
+use super::sim::{
+    generate_blocks, reset_paddle, spawn_docked_ball, Ball, Paddle, Scoreboard, ServeCountdown,
+};
+use crate::GameState;
+
+// How long the "Level N cleared" screen stays up before the next level starts
+const LEVEL_CLEARED_SECONDS: f32 = 2.0;
+
+// The level currently being played, starting at 1
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct Level(pub usize);
+
+impl Default for Level {
+    fn default() -> Self {
+        Level(1)
+    }
+}
+
+// How the seed for the generated levels is picked when a new game starts
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LevelSeed {
+    // A different seed every game
+    #[default]
+    Random,
+    // Always the same seed, to replay a shared game or to get predictable levels in tests
+    Fixed(u64),
+}
+
+// The levels that make up a game, in the order they are played. Once the hand made levels run
+// out, the rest are generated from `seed` and get a little harder each time.
+#[derive(Resource, Debug, Clone)]
+pub struct Levels {
+    pub layouts: Vec<LevelLayout>,
+    pub seed: u64,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Levels {
+            layouts: vec![LevelLayout::full_grid()],
+            seed: 0,
+        }
+    }
+}
+
+impl Levels {
+    // Get the layout for a level number (starting at 1)
+    pub fn layout(&self, level: Level) -> LevelLayout {
+        let index = level.0.max(1) - 1;
+
+        match self.layouts.get(index) {
+            Some(layout) => layout.clone(),
+            None => {
+                let difficulty = (index + 1 - self.layouts.len()) as u32;
+                generate_level(level_seed(self.seed, level.0), difficulty)
+            }
+        }
+    }
+}
+
+// Newtype to use a `Timer` for the level cleared screen as a resource
+#[derive(Resource, Deref, DerefMut)]
+pub(super) struct LevelClearedTimer(Timer);
+
+pub(super) fn level_cleared_setup(
+    mut commands: Commands,
+    level: Res<Level>,
+    scoreboard: Res<Scoreboard>,
+    mut level_cleared: EventWriter<LevelCleared>,
+) {
+    level_cleared.send(LevelCleared { level: level.0, score: scoreboard.score });
+    commands.insert_resource(LevelClearedTimer(Timer::from_seconds(LEVEL_CLEARED_SECONDS, TimerMode::Once)));
+}
+
+// When the level cleared screen is done, set up the next level and get back to playing
+pub(super) fn advance_level(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timer: ResMut<LevelClearedTimer>,
+    mut level: ResMut<Level>,
+    levels: Res<Levels>,
+    ball_query: Query<Entity, With<Ball>>,
+    mut paddle_query: Query<&mut Transform, With<Paddle>>,
+    block_query: Query<Entity, With<Block>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    level.0 += 1;
+    let layout = levels.layout(*level);
+
+    // Every level starts with the paddle back where it belongs and a fresh ball waiting on it
+    for entity in &ball_query {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<ServeCountdown>();
+    for mut transform in &mut paddle_query {
+        reset_paddle(&mut transform, &layout);
+        spawn_docked_ball(&mut commands, transform.translation.x);
+    }
+
+    // Steel blocks don't need to be broken, so clear out whatever is left before the next level
+    for entity in &block_query {
+        commands.entity(entity).despawn();
+    }
+    generate_blocks(&mut commands, &layout);
+    game_state.set(GameState::InGame);
+}
@@ -0,0 +1,312 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState},
+    prelude::*,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::block::BlockKind;
+use super::layout::{BlockGrid, BlockSpec, LevelLayout};
+use super::level::Levels;
+use super::power_up::WIDE_PADDLE_FACTOR;
+use super::sim::{LEFT_WALL, PADDLE_PADDING, PADDLE_SIZE, RIGHT_WALL, WALL_THICKNESS};
+
+// The levels of the game, in the order they are played
+const LEVEL_PATHS: [&str; 3] = [
+    "levels/level_01.level.ron",
+    "levels/level_02.level.ron",
+    "levels/level_03.level.ron",
+];
+
+// Characters that leave a cell of the grid empty
+const EMPTY_CELLS: [char; 2] = ['.', ' '];
+
+// This plugin loads level files from the `assets/levels` folder and hands them to the simulation
+// through the `Levels` resource. If a level can't be loaded the error is logged and the level
+// is left out. When none of them load, the built-in grid is played instead.
+pub struct LevelFilePlugin;
+
+impl Plugin for LevelFilePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset::<LevelAsset>()
+            .init_asset_loader::<LevelLoader>()
+            .add_systems(Startup, load_levels)
+            .add_systems(Update, (use_loaded_levels, report_failed_levels));
+    }
+}
+
+// A level loaded from a `.level.ron` file
+#[derive(Asset, TypePath, Debug)]
+pub struct LevelAsset {
+    pub layout: LevelLayout,
+}
+
+// Handles to the level files, kept around so the assets stay loaded
+#[derive(Resource)]
+struct LevelHandles(Vec<Handle<LevelAsset>>);
+
+// On-disk format of a level. The blocks are drawn as an ASCII grid, top row first, where every
+// character is looked up in the legend and `.` or a space leaves a gap:
+//
+// (
+//     name: "The Wall",
+//     rows: [
+//         "TTSXXSTT",
+//         "#.####.#",
+//     ],
+//     legend: {
+//         '#': (color: (1.0, 0.75, 0.8)),
+//         'T': (color: (0.4, 0.4, 1.0), kind: Normal(hits: 3)),
+//         'S': (color: (0.75, 0.75, 0.75), kind: Steel),
+//         'X': (color: (1.0, 0.3, 0.0), kind: Explosive),
+//     },
+//     paddle_width: 120.0,
+// )
+//
+// Blocks without a `kind` break in one hit. The bottom row sits just above the paddle, the way
+// the original grid always did.
+#[derive(Deserialize, Debug)]
+struct LevelFile {
+    name: String,
+    rows: Vec<String>,
+    legend: HashMap<char, LegendEntry>,
+    #[serde(default = "default_paddle_width")]
+    paddle_width: f32,
+}
+
+#[derive(Deserialize, Debug)]
+struct LegendEntry {
+    color: (f32, f32, f32),
+    #[serde(default)]
+    kind: BlockKind,
+}
+
+fn default_paddle_width() -> f32 {
+    PADDLE_SIZE.x
+}
+
+// The widest paddle a level can ask for. The paddle has to fit between the walls, with room to
+// spare for the wide paddle power-up to make it wider still.
+fn max_paddle_width() -> f32 {
+    (RIGHT_WALL - LEFT_WALL - WALL_THICKNESS - 2.0 * PADDLE_PADDING) / WIDE_PADDLE_FACTOR
+}
+
+#[derive(Error, Debug)]
+pub enum LevelFileError {
+    #[error("could not read level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse level file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("level has no rows of blocks")]
+    Empty,
+    #[error("level has {rows} rows but only {max} fit above the paddle")]
+    TooManyRows { rows: usize, max: usize },
+    #[error("row {row} has {columns} columns but only {max} fit between the walls")]
+    TooManyColumns { row: usize, columns: usize, max: usize },
+    #[error("row {row}, column {column} uses '{block}' which is not in the legend")]
+    UnknownBlock { row: usize, column: usize, block: char },
+    #[error("paddle width {0} must be more than 0 and at most {}", max_paddle_width())]
+    BadPaddleWidth(f32),
+    #[error("level has no blocks that can be broken, so it could never be cleared")]
+    NoBreakableBlocks,
+}
+
+impl LevelFile {
+    // Turn the ASCII grid into block positions, checking that everything fits in the arena
+    fn into_layout(self) -> Result<LevelLayout, LevelFileError> {
+        let n_rows = self.rows.len();
+        let n_columns = self.rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+
+        if n_rows == 0 || n_columns == 0 {
+            return Err(LevelFileError::Empty);
+        }
+        if n_rows > BlockGrid::max_rows() {
+            return Err(LevelFileError::TooManyRows { rows: n_rows, max: BlockGrid::max_rows() });
+        }
+        // Written this way round so a NaN width is turned down too
+        if !(self.paddle_width > 0.0 && self.paddle_width <= max_paddle_width()) {
+            return Err(LevelFileError::BadPaddleWidth(self.paddle_width));
+        }
+
+        let mut blocks = Vec::new();
+        for (row_index, row) in self.rows.iter().enumerate() {
+            // Rows are written top to bottom but the grid counts from the bottom
+            let grid_row = n_rows - 1 - row_index;
+            let columns = row.chars().count();
+            if columns > BlockGrid::max_columns() {
+                return Err(LevelFileError::TooManyColumns {
+                    row: row_index + 1,
+                    columns,
+                    max: BlockGrid::max_columns(),
+                });
+            }
+
+            for (column, block) in row.chars().enumerate() {
+                if EMPTY_CELLS.contains(&block) {
+                    continue;
+                }
+                let Some(entry) = self.legend.get(&block) else {
+                    return Err(LevelFileError::UnknownBlock {
+                        row: row_index + 1,
+                        column: column + 1,
+                        block,
+                    });
+                };
+                let (r, g, b) = entry.color;
+                blocks.push(BlockSpec {
+                    position: BlockGrid::cell_position(grid_row, column, n_columns),
+                    color: Color::rgb(r, g, b),
+                    kind: entry.kind,
+                });
+            }
+        }
+
+        // Steel blocks never break, so a level of nothing but steel (or of nothing at all) could
+        // never be cleared
+        if blocks.iter().all(|block| !block.kind.is_breakable()) {
+            return Err(LevelFileError::NoBreakableBlocks);
+        }
+
+        Ok(LevelLayout {
+            name: self.name,
+            blocks,
+            paddle_width: self.paddle_width,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = LevelAsset;
+    type Settings = ();
+    type Error = LevelFileError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<LevelAsset, LevelFileError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let level_file: LevelFile = ron::de::from_bytes(&bytes)?;
+
+            Ok(LevelAsset {
+                layout: level_file.into_layout()?,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+fn load_levels(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LevelHandles(
+        LEVEL_PATHS.iter().map(|path| asset_server.load(*path)).collect()
+    ));
+}
+
+// Whenever a level file is loaded (or reloaded), rebuild the list of levels the simulation plays
+fn use_loaded_levels(
+    mut asset_events: EventReader<AssetEvent<LevelAsset>>,
+    level_assets: Res<Assets<LevelAsset>>,
+    level_handles: Res<LevelHandles>,
+    mut levels: ResMut<Levels>,
+) {
+    let mut changed = false;
+    for event in asset_events.read() {
+        if let AssetEvent::Added { id } | AssetEvent::Modified { id } = event {
+            changed |= level_handles.0.iter().any(|handle| handle.id() == *id);
+        }
+    }
+
+    if changed {
+        let layouts: Vec<LevelLayout> = level_handles.0
+            .iter()
+            .filter_map(|handle| level_assets.get(handle))
+            .map(|level| level.layout.clone())
+            .collect();
+
+        if !layouts.is_empty() {
+            levels.layouts = layouts;
+        }
+    }
+}
+
+// The asset server already logs why a file failed to load, so we just point out which level is
+// being left out
+fn report_failed_levels(
+    asset_server: Res<AssetServer>,
+    level_handles: Res<LevelHandles>,
+    mut reported: Local<Vec<bool>>,
+) {
+    reported.resize(level_handles.0.len(), false);
+
+    for (index, handle) in level_handles.0.iter().enumerate() {
+        if !reported[index] && asset_server.get_load_state(handle) == Some(LoadState::Failed) {
+            error!("Level file {} could not be loaded, skipping that level", LEVEL_PATHS[index]);
+            reported[index] = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(text: &str) -> Result<LevelLayout, LevelFileError> {
+        ron::de::from_str::<LevelFile>(text)?.into_layout()
+    }
+
+    fn level_with_paddle_width(paddle_width: &str) -> String {
+        format!(
+            r#"(
+                name: "Test",
+                rows: ["XX"],
+                legend: {{ 'X': (color: (1.0, 1.0, 1.0)) }},
+                paddle_width: {paddle_width},
+            )"#
+        )
+    }
+
+    #[test]
+    fn paddle_width_is_read() {
+        let layout = load(&level_with_paddle_width("200.0")).unwrap();
+        assert_eq!(layout.paddle_width, 200.0);
+        assert_eq!(layout.blocks.len(), 2);
+    }
+
+    #[test]
+    fn paddle_width_must_be_positive() {
+        for width in ["0.0", "-10.0"] {
+            let error = load(&level_with_paddle_width(width)).unwrap_err();
+            assert!(matches!(error, LevelFileError::BadPaddleWidth(_)), "{width}: {error}");
+        }
+    }
+
+    #[test]
+    fn paddle_width_must_leave_room_for_wide_paddle() {
+        // Still fits between the walls, but not once the wide paddle power-up is caught
+        let error = load(&level_with_paddle_width("600.0")).unwrap_err();
+        assert!(matches!(error, LevelFileError::BadPaddleWidth(width) if width == 600.0));
+
+        let widest = max_paddle_width();
+        let layout = load(&level_with_paddle_width(&format!("{widest:?}"))).unwrap();
+        assert_eq!(layout.paddle_width, widest);
+    }
+
+    #[test]
+    fn paddle_width_must_be_a_number() {
+        for width in ["NaN", "inf"] {
+            let error = load(&level_with_paddle_width(width)).unwrap_err();
+            assert!(matches!(error, LevelFileError::BadPaddleWidth(_)), "{width}: {error}");
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+
+use super::events::BlockDestroyed;
+use super::rng::SeededRng;
+use super::sim::OnGameScreen;
+use crate::DisplayQuality;
+
+const PARTICLE_SIZE: f32 = 6.0;
+const PARTICLE_MIN_SPEED: f32 = 100.0;
+const PARTICLE_MAX_SPEED: f32 = 300.0;
+const PARTICLE_LIFETIME: f32 = 0.6;
+// Particles are a little brighter than the block they came from, so they catch the bloom
+const PARTICLE_GLOW: f32 = 1.5;
+
+// A bit of a broken block flying away and fading out
+#[derive(Component)]
+pub(super) struct Particle {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+// Particles are only for show, so they get their own random numbers and don't change how a
+// seeded game plays out
+#[derive(Resource)]
+pub(super) struct ParticleRng(SeededRng);
+
+impl Default for ParticleRng {
+    fn default() -> Self {
+        ParticleRng(SeededRng::new(0))
+    }
+}
+
+// Burst every broken block into particles of its color. How many depends on the display
+// quality, which is `Medium` for apps that don't have the setting.
+pub(super) fn spawn_block_particles(
+    mut commands: Commands,
+    mut destroyed_blocks: EventReader<BlockDestroyed>,
+    mut rng: ResMut<ParticleRng>,
+    quality: Option<Res<DisplayQuality>>,
+) {
+    let count = quality.map_or(DisplayQuality::Medium, |quality| *quality).particles_per_block();
+
+    for destroyed in destroyed_blocks.read() {
+        let [red, green, blue, alpha] = destroyed.color.as_rgba_f32();
+        let color = Color::rgba(
+            red * PARTICLE_GLOW,
+            green * PARTICLE_GLOW,
+            blue * PARTICLE_GLOW,
+            alpha,
+        );
+
+        for _ in 0..count {
+            let angle = rng.0.next_f32() * std::f32::consts::TAU;
+            let speed = PARTICLE_MIN_SPEED + rng.0.next_f32() * (PARTICLE_MAX_SPEED - PARTICLE_MIN_SPEED);
+
+            commands.spawn((
+                SpriteBundle {
+                    transform: Transform::from_translation(destroyed.position),
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(PARTICLE_SIZE)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Particle {
+                    velocity: Vec2::from_angle(angle) * speed,
+                    timer: Timer::from_seconds(PARTICLE_LIFETIME, TimerMode::Once),
+                },
+                OnGameScreen,
+            ));
+        }
+    }
+}
+
+// Move the particles, fade and shrink them, and clean them up when they are done
+pub(super) fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in &mut particle_query {
+        particle.timer.tick(time.delta());
+        if particle.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        let remaining = particle.timer.percent_left();
+        transform.scale = Vec3::splat(remaining);
+        sprite.color.set_a(remaining);
+    }
+}
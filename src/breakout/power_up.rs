@@ -0,0 +1,314 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    sprite::collide_aabb::collide,
+};
+
+use super::block::{Block, BlockHit, BlockKind};
+use super::events::{BlockDestroyed, HealthChanged, LivesChanged};
+use super::level::Levels;
+use super::rng::SeededRng;
+use super::sim::{
+    spawn_ball, Ball, Docked, GameMode, OnGameScreen, Paddle, Scoreboard, Velocity, BOTTOM_WALL,
+    STARTING_HEALTH, STARTING_LIVES,
+};
+
+// Capsules
+pub const CAPSULE_SIZE: Vec3 = Vec3::new(40.0, 15.0, 1.0);
+const CAPSULE_FALL_SPEED: f32 = 150.0;
+// Chance that a breaking block drops a capsule
+const DROP_CHANCE: f32 = 0.15;
+
+// Effects
+const SLOW_BALL_FACTOR: f32 = 0.6;
+const FAST_PADDLE_FACTOR: f32 = 1.5;
+pub(super) const WIDE_PADDLE_FACTOR: f32 = 1.5;
+const HEALTH_PICKUP: usize = 15;
+// Multi-ball adds two balls next to every ball in play, this many degrees to either side
+const MULTI_BALL_SPREAD: f32 = 20.0;
+
+// Everything a capsule can do when the paddle catches it. To add a new power-up, add a variant
+// here and fill in the `match`es below; the capsules, timers and reverting are taken care of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum PowerUp {
+    SlowBall,
+    FastPaddle,
+    WidePaddle,
+    Health,
+    Bomb,
+    MultiBall,
+}
+
+// Every power-up that can drop from a block
+pub const POWER_UPS: [PowerUp; 6] = [
+    PowerUp::SlowBall,
+    PowerUp::FastPaddle,
+    PowerUp::WidePaddle,
+    PowerUp::Health,
+    PowerUp::Bomb,
+    PowerUp::MultiBall,
+];
+
+// Multipliers that the active power-ups apply on top of the normal game rules
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+#[reflect(Resource)]
+pub struct PowerUpModifiers {
+    pub ball_speed: f32,
+    pub paddle_speed: f32,
+}
+
+impl Default for PowerUpModifiers {
+    fn default() -> Self {
+        PowerUpModifiers {
+            ball_speed: 1.0,
+            paddle_speed: 1.0,
+        }
+    }
+}
+
+// The parts of the game a power-up can change
+#[derive(SystemParam)]
+pub struct PowerUpTargets<'w, 's> {
+    commands: Commands<'w, 's>,
+    scoreboard: ResMut<'w, Scoreboard>,
+    game_mode: Res<'w, GameMode>,
+    modifiers: ResMut<'w, PowerUpModifiers>,
+    rng: ResMut<'w, PowerUpRng>,
+    paddles: Query<'w, 's, &'static mut Transform, With<Paddle>>,
+    balls: Query<
+        'w,
+        's,
+        (&'static Transform, &'static Velocity),
+        (With<Ball>, Without<Docked>, Without<Paddle>),
+    >,
+    blocks: Query<'w, 's, (Entity, &'static mut Block)>,
+    block_hits: EventWriter<'w, BlockHit>,
+    health_changes: EventWriter<'w, HealthChanged>,
+    lives_changes: EventWriter<'w, LivesChanged>,
+}
+
+impl PowerUp {
+    // How long the effect lasts, or `None` if it happens once and is done
+    pub fn duration(&self) -> Option<f32> {
+        match self {
+            PowerUp::SlowBall => Some(10.0),
+            PowerUp::FastPaddle => Some(10.0),
+            PowerUp::WidePaddle => Some(15.0),
+            PowerUp::Health | PowerUp::Bomb | PowerUp::MultiBall => None,
+        }
+    }
+
+    fn apply(&self, targets: &mut PowerUpTargets) {
+        match self {
+            PowerUp::SlowBall => targets.modifiers.ball_speed *= SLOW_BALL_FACTOR,
+            PowerUp::FastPaddle => targets.modifiers.paddle_speed *= FAST_PADDLE_FACTOR,
+            PowerUp::WidePaddle => {
+                for mut transform in &mut targets.paddles {
+                    transform.scale.x *= WIDE_PADDLE_FACTOR;
+                }
+            }
+            // Health is what keeps the game going, so in lives mode it gives back a life instead
+            PowerUp::Health => match *targets.game_mode {
+                GameMode::Health => {
+                    let previous = targets.scoreboard.health;
+                    targets.scoreboard.health = (previous + HEALTH_PICKUP).min(STARTING_HEALTH);
+                    if targets.scoreboard.health != previous {
+                        targets.health_changes.send(HealthChanged {
+                            previous,
+                            current: targets.scoreboard.health,
+                        });
+                    }
+                }
+                GameMode::Lives => {
+                    let previous = targets.scoreboard.lives;
+                    targets.scoreboard.lives = (previous + 1).min(STARTING_LIVES);
+                    if targets.scoreboard.lives != previous {
+                        targets.lives_changes.send(LivesChanged {
+                            previous,
+                            current: targets.scoreboard.lives,
+                        });
+                    }
+                }
+            },
+            PowerUp::Bomb => {
+                // Turn a random breakable block into an explosive one and set it off
+                let breakable: Vec<Entity> = targets.blocks
+                    .iter()
+                    .filter(|(_, block)| block.kind.is_breakable())
+                    .map(|(entity, _)| entity)
+                    .collect();
+                if breakable.is_empty() {
+                    return;
+                }
+                let target = breakable[targets.rng.0.below(breakable.len())];
+                if let Ok((_, mut block)) = targets.blocks.get_mut(target) {
+                    *block = Block::new(BlockKind::Explosive);
+                    targets.block_hits.send(BlockHit(target));
+                }
+            }
+            PowerUp::MultiBall => {
+                // Split every ball in play into three
+                for (transform, velocity) in &targets.balls {
+                    for angle in [-MULTI_BALL_SPREAD, MULTI_BALL_SPREAD] {
+                        let rotation = Vec2::from_angle(angle.to_radians());
+                        let split_velocity = rotation.rotate(velocity.0);
+                        spawn_ball(&mut targets.commands, transform.translation, split_velocity);
+                    }
+                }
+            }
+        }
+    }
+
+    // Undo a timed effect when it runs out
+    fn revert(&self, targets: &mut PowerUpTargets) {
+        match self {
+            PowerUp::SlowBall => targets.modifiers.ball_speed /= SLOW_BALL_FACTOR,
+            PowerUp::FastPaddle => targets.modifiers.paddle_speed /= FAST_PADDLE_FACTOR,
+            PowerUp::WidePaddle => {
+                for mut transform in &mut targets.paddles {
+                    transform.scale.x /= WIDE_PADDLE_FACTOR;
+                }
+            }
+            PowerUp::Health | PowerUp::Bomb | PowerUp::MultiBall => {}
+        }
+    }
+}
+
+// A falling capsule that gives its power-up to the paddle that catches it
+#[derive(Component)]
+pub struct PowerUpCapsule(pub PowerUp);
+
+// A timed power-up that is currently in effect
+#[derive(Debug)]
+pub struct ActivePowerUp {
+    pub power_up: PowerUp,
+    pub timer: Timer,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct ActivePowerUps(pub Vec<ActivePowerUp>);
+
+// Random numbers for drops and effects, seeded from the level seed so a game can be replayed
+#[derive(Resource)]
+pub struct PowerUpRng(pub SeededRng);
+
+impl Default for PowerUpRng {
+    fn default() -> Self {
+        PowerUpRng(SeededRng::new(0))
+    }
+}
+
+// Start every game without any power-ups
+pub(super) fn power_up_setup(
+    mut active: ResMut<ActivePowerUps>,
+    mut modifiers: ResMut<PowerUpModifiers>,
+    mut rng: ResMut<PowerUpRng>,
+    levels: Res<Levels>,
+) {
+    active.0.clear();
+    *modifiers = PowerUpModifiers::default();
+    rng.0 = SeededRng::new(levels.seed);
+}
+
+// Sometimes breaking a block drops a capsule
+pub(super) fn drop_power_ups(
+    mut commands: Commands,
+    mut destroyed_blocks: EventReader<BlockDestroyed>,
+    mut rng: ResMut<PowerUpRng>,
+) {
+    for destroyed in destroyed_blocks.read() {
+        if !rng.0.chance(DROP_CHANCE) {
+            continue;
+        }
+        let power_up = POWER_UPS[rng.0.below(POWER_UPS.len())];
+
+        commands.spawn((
+            TransformBundle::from_transform(
+                Transform::from_translation(destroyed.position.truncate().extend(CAPSULE_SIZE.z))
+                    .with_scale(CAPSULE_SIZE)
+            ),
+            PowerUpCapsule(power_up),
+            Velocity(Vec2::new(0.0, -CAPSULE_FALL_SPEED)),
+            Name::new(format!("{power_up:?} Capsule")),
+            OnGameScreen,
+        ));
+    }
+}
+
+// Give the paddle the power-up of any capsule it touches, and get rid of the ones it missed
+pub(super) fn collect_power_ups(
+    mut commands: Commands,
+    capsule_query: Query<(Entity, &Transform, &PowerUpCapsule), Without<Paddle>>,
+    mut active: ResMut<ActivePowerUps>,
+    mut targets: PowerUpTargets,
+) {
+    let paddles: Vec<Transform> = targets.paddles.iter().copied().collect();
+
+    for (entity, capsule_transform, capsule) in &capsule_query {
+        let caught = paddles.iter().any(|paddle_transform| {
+            collide(
+                capsule_transform.translation,
+                capsule_transform.scale.truncate(),
+                paddle_transform.translation,
+                paddle_transform.scale.truncate(),
+            ).is_some()
+        });
+
+        if caught {
+            commands.entity(entity).despawn();
+            let power_up = capsule.0;
+
+            match power_up.duration() {
+                // Catching a power-up that is already running just makes it last longer
+                Some(seconds) => match active.0.iter_mut().find(|active| active.power_up == power_up) {
+                    Some(running) => running.timer.reset(),
+                    None => {
+                        power_up.apply(&mut targets);
+                        active.0.push(ActivePowerUp {
+                            power_up,
+                            timer: Timer::from_seconds(seconds, TimerMode::Once),
+                        });
+                    }
+                },
+                None => power_up.apply(&mut targets),
+            }
+        } else if capsule_transform.translation.y < BOTTOM_WALL {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Count down the timed power-ups and undo the ones that run out
+pub(super) fn tick_power_ups(
+    time: Res<Time>,
+    mut active: ResMut<ActivePowerUps>,
+    mut targets: PowerUpTargets,
+) {
+    for running in &mut active.0 {
+        running.timer.tick(time.delta());
+    }
+
+    active.0.retain(|running| {
+        if running.timer.finished() {
+            running.power_up.revert(&mut targets);
+            false
+        } else {
+            true
+        }
+    });
+}
+
+// Power-ups don't carry over to the next level
+pub(super) fn clear_power_ups(
+    mut commands: Commands,
+    capsule_query: Query<Entity, With<PowerUpCapsule>>,
+    mut active: ResMut<ActivePowerUps>,
+    mut targets: PowerUpTargets,
+) {
+    for entity in &capsule_query {
+        commands.entity(entity).despawn();
+    }
+    for running in active.0.drain(..) {
+        running.power_up.revert(&mut targets);
+    }
+}
@@ -0,0 +1,486 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    utils::HashSet,
+};
+
+use super::level::{Level, Levels};
+use super::particles::{spawn_block_particles, update_particles, ParticleRng};
+use super::block::Block;
+use super::power_up::{PowerUp, PowerUpCapsule};
+use super::sim::{
+    Ball, BallSpeed, BlockColor, Docked, GameMode, OnGameScreen, Paddle, PaddleBounce,
+    PausedState, Scoreboard, ServeCountdown, Wall, MAX_BALL_SPEED_TIER, STARTING_HEALTH,
+    STARTING_LIVES,
+};
+use crate::{despawn_screen, GameState};
+
+const PADDLE_COLOR: Color = Color::LIME_GREEN;
+const BALL_COLOR: Color = Color::rgba(0.0, 0.5, 1.0, 1.0);
+const WALL_COLOR: Color = Color::RED;
+// How much brighter a block gets for every extra hit it can take
+const TOUGH_BLOCK_GLOW: f32 = 0.6;
+// The line showing where a ball waiting on the paddle will go
+const AIM_INDICATOR_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.6);
+const AIM_INDICATOR_SIZE: Vec2 = Vec2::new(60.0, 3.0);
+// Capsules are bright enough to bloom
+const CAPSULE_GLOW: f32 = 2.0;
+
+// Scoreboard
+const SCOREBOARD_FONT_SIZE: f32 = 40.0;
+const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
+const TEXT_COLOR: Color = Color::WHITE;
+const SCORE_COLOR: Color = Color::GREEN;
+static HEALTH_COLOR: Color = Color::rgb(0.0, 1.0, 0.0);
+const LEVEL_COLOR: Color = Color::ORANGE;
+const SPEED_COLOR: Color = Color::CYAN;
+const MAX_SPEED_COLOR: Color = Color::RED;
+
+// Countdown before a new ball is served
+const SERVE_COUNTDOWN_FONT_SIZE: f32 = 120.0;
+
+// Level cleared screen
+const LEVEL_CLEARED_FONT_SIZE: f32 = 80.0;
+const NEXT_LEVEL_FONT_SIZE: f32 = 40.0;
+
+const BACKGROUND_COLOR: Color = Color::BLACK;
+
+// This plugin gives the entities spawned by `BreakoutSimPlugin` something to look at: sprites
+// for the paddle, walls, blocks and power-up capsules, a mesh for the ball, particles for broken
+// blocks, the scoreboard text and the screen shown between levels
+pub struct BreakoutRenderPlugin;
+
+impl Plugin for BreakoutRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(ClearColor(BACKGROUND_COLOR))
+            .init_resource::<BallMesh>()
+            .init_resource::<ParticleRng>()
+            .add_systems(OnEnter(GameState::NewGame), scoreboard_setup)
+            .add_systems(
+                Update,
+                (
+                    add_ball_visuals,
+                    add_sprite_visuals::<Paddle>(PADDLE_COLOR),
+                    add_sprite_visuals::<Wall>(WALL_COLOR),
+                    add_block_visuals,
+                    update_block_colors,
+                    add_capsule_visuals,
+                    update_aim_indicators,
+                    spawn_block_particles,
+                    update_particles.run_if(not(in_state(PausedState::Paused))),
+                ),
+            )
+            .add_systems(
+                Update,
+                (update_scoreboard, update_serve_countdown).run_if(in_state(PausedState::Running)),
+            )
+            .add_systems(OnEnter(GameState::LevelCleared), level_cleared_setup)
+            .add_systems(
+                OnExit(GameState::LevelCleared),
+                despawn_screen::<OnLevelClearedScreen>,
+            );
+    }
+}
+
+// Tag component used to find the scoreboard text
+#[derive(Component)]
+struct ScoreboardText;
+
+// Points the way a docked ball will be launched, for as long as the ball stays docked
+#[derive(Component)]
+struct AimIndicator(Entity);
+
+// Tag component used to find the serve countdown text
+#[derive(Component)]
+struct ServeCountdownText;
+
+// Tag component used to tag entities added on the level cleared screen
+#[derive(Component)]
+struct OnLevelClearedScreen;
+
+// The ball mesh and material are shared by every ball we spawn
+#[derive(Resource)]
+struct BallMesh {
+    mesh: Mesh2dHandle,
+    material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for BallMesh {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Circle::default().into())
+            .into();
+        let material = world
+            .resource_mut::<Assets<ColorMaterial>>()
+            .add(ColorMaterial::from(BALL_COLOR));
+
+        BallMesh { mesh, material }
+    }
+}
+
+fn add_ball_visuals(
+    mut commands: Commands,
+    ball_mesh: Res<BallMesh>,
+    ball_query: Query<(Entity, &Transform), Added<Ball>>,
+) {
+    for (entity, transform) in &ball_query {
+        commands.entity(entity).insert(MaterialMesh2dBundle {
+            mesh: ball_mesh.mesh.clone(),
+            material: ball_mesh.material.clone(),
+            transform: *transform,
+            ..default()
+        });
+    }
+}
+
+// Generic system that gives a plain colored sprite to every new entity with the `T` component
+fn add_sprite_visuals<T: Component>(
+    color: Color,
+) -> impl FnMut(Commands, Query<(Entity, &Transform), Added<T>>) {
+    move |mut commands, query| {
+        for (entity, transform) in &query {
+            commands.entity(entity).insert(SpriteBundle {
+                transform: *transform,
+                sprite: Sprite {
+                    color,
+                    ..default()
+                },
+                ..default()
+            });
+        }
+    }
+}
+
+// Blocks get the color their level gave them, glowing brighter the more hits they have left
+fn block_color(base: Color, block: &Block) -> Color {
+    let brightness = 1.0 + TOUGH_BLOCK_GLOW * block.hit_points.saturating_sub(1) as f32;
+    let [red, green, blue, alpha] = base.as_rgba_f32();
+
+    Color::rgba(red * brightness, green * brightness, blue * brightness, alpha)
+}
+
+fn add_block_visuals(
+    mut commands: Commands,
+    block_query: Query<(Entity, &Transform, &Block, &BlockColor), Added<Block>>,
+) {
+    for (entity, transform, block, color) in &block_query {
+        commands.entity(entity).insert(SpriteBundle {
+            transform: *transform,
+            sprite: Sprite {
+                color: block_color(**color, block),
+                ..default()
+            },
+            ..default()
+        });
+    }
+}
+
+// Dim blocks as they take damage
+fn update_block_colors(
+    mut block_query: Query<(&Block, &BlockColor, &mut Sprite), Changed<Block>>,
+) {
+    for (block, color, mut sprite) in &mut block_query {
+        sprite.color = block_color(**color, block);
+    }
+}
+
+// Give every ball waiting on the paddle an aim indicator, keep them pointing the right way, and
+// remove them once the ball is launched
+fn update_aim_indicators(
+    mut commands: Commands,
+    paddle_bounce: Res<PaddleBounce>,
+    ball_query: Query<(Entity, &Transform, &Docked), With<Ball>>,
+    mut indicator_query: Query<(Entity, &AimIndicator, &mut Transform), Without<Ball>>,
+) {
+    let mut has_indicator = HashSet::new();
+
+    for (indicator, AimIndicator(ball), mut transform) in &mut indicator_query {
+        let Ok((_, ball_transform, docked)) = ball_query.get(*ball) else {
+            commands.entity(indicator).despawn();
+            continue;
+        };
+
+        *transform = aim_indicator_transform(ball_transform, docked, &paddle_bounce);
+        has_indicator.insert(*ball);
+    }
+
+    for (ball, ball_transform, docked) in &ball_query {
+        if has_indicator.contains(&ball) {
+            continue;
+        }
+
+        commands.spawn((
+            SpriteBundle {
+                transform: aim_indicator_transform(ball_transform, docked, &paddle_bounce),
+                sprite: Sprite {
+                    color: AIM_INDICATOR_COLOR,
+                    custom_size: Some(AIM_INDICATOR_SIZE),
+                    ..default()
+                },
+                ..default()
+            },
+            AimIndicator(ball),
+            Name::new("Aim Indicator"),
+            OnGameScreen,
+        ));
+    }
+}
+
+// The indicator starts at the edge of the ball and points along the launch direction
+fn aim_indicator_transform(ball_transform: &Transform, docked: &Docked, paddle_bounce: &PaddleBounce) -> Transform {
+    let direction = docked.direction(paddle_bounce);
+    let distance = ball_transform.scale.y / 2.0 + AIM_INDICATOR_SIZE.x / 2.0;
+
+    Transform::from_translation(ball_transform.translation + (direction * distance).extend(0.0))
+        .with_rotation(Quat::from_rotation_z(direction.y.atan2(direction.x)))
+}
+
+// Every power-up gets its own capsule color
+fn capsule_color(power_up: PowerUp) -> Color {
+    let color = match power_up {
+        PowerUp::SlowBall => Color::CYAN,
+        PowerUp::FastPaddle => Color::YELLOW,
+        PowerUp::WidePaddle => PADDLE_COLOR,
+        PowerUp::Health => Color::WHITE,
+        PowerUp::Bomb => Color::ORANGE_RED,
+        PowerUp::MultiBall => BALL_COLOR,
+    };
+    let [red, green, blue, alpha] = color.as_rgba_f32();
+
+    Color::rgba(red * CAPSULE_GLOW, green * CAPSULE_GLOW, blue * CAPSULE_GLOW, alpha)
+}
+
+fn add_capsule_visuals(
+    mut commands: Commands,
+    capsule_query: Query<(Entity, &Transform, &PowerUpCapsule), Added<PowerUpCapsule>>,
+) {
+    for (entity, transform, capsule) in &capsule_query {
+        commands.entity(entity).insert(SpriteBundle {
+            transform: *transform,
+            sprite: Sprite {
+                color: capsule_color(capsule.0),
+                ..default()
+            },
+            ..default()
+        });
+    }
+}
+
+fn scoreboard_setup(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new(
+                "Score: ",
+                TextStyle {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font_size: SCOREBOARD_FONT_SIZE,
+                color: SCORE_COLOR,
+                ..default()
+            }),
+            TextSection::new(
+                "\nHealth: ",
+                TextStyle {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font_size: SCOREBOARD_FONT_SIZE,
+                color: HEALTH_COLOR,
+                ..default()
+            }),
+            TextSection::new(
+                "\nLevel: ",
+                TextStyle {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font_size: SCOREBOARD_FONT_SIZE,
+                color: LEVEL_COLOR,
+                ..default()
+            }),
+            TextSection::new(
+                "\nSpeed: ",
+                TextStyle {
+                    font_size: SCOREBOARD_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ),
+            TextSection::from_style(TextStyle {
+                font_size: SCOREBOARD_FONT_SIZE,
+                color: SPEED_COLOR,
+                ..default()
+            }),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: SCOREBOARD_TEXT_PADDING,
+            left: SCOREBOARD_TEXT_PADDING,
+            ..default()
+        }),
+        ScoreboardText,
+        Name::new("Scoreboard"),
+        OnGameScreen,
+    ));
+
+    // Big number in the middle of the screen while waiting to serve a new ball, empty otherwise
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Name::new("Serve Countdown"),
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: SERVE_COUNTDOWN_FONT_SIZE,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                ),
+                ServeCountdownText,
+            ));
+        });
+}
+
+fn update_scoreboard(
+    scoreboard: Res<Scoreboard>,
+    level: Res<Level>,
+    ball_speed: Res<BallSpeed>,
+    game_mode: Res<GameMode>,
+    mut query: Query<&mut Text, With<ScoreboardText>>,
+) {
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+    text.sections[1].value = scoreboard.score.to_string();
+    text.sections[5].value = level.0.to_string();
+
+    // Lives mode shows the lives left in place of the health, faded the same way
+    let health: f32 = match *game_mode {
+        GameMode::Health => {
+            text.sections[2].value = "\nHealth: ".into();
+            text.sections[3].value = scoreboard.health.to_string();
+            scoreboard.health as f32 / STARTING_HEALTH as f32 * 100.0
+        }
+        GameMode::Lives => {
+            text.sections[2].value = "\nLives: ".into();
+            text.sections[3].value = scoreboard.lives.to_string();
+            scoreboard.lives as f32 / STARTING_LIVES as f32 * 100.0
+        }
+    };
+
+    // Speed is shown as a tier out of the maximum, and turns red once it can't go any faster
+    text.sections[7].value = if ball_speed.is_max() {
+        String::from("MAX")
+    } else {
+        format!("{}/{}", ball_speed.tier, MAX_BALL_SPEED_TIER)
+    };
+    text.sections[7].style.color = if ball_speed.is_max() { MAX_SPEED_COLOR } else { SPEED_COLOR };
+
+    // Fade color from green to red as health decreases
+    let red: f32;
+    let green: f32;
+    if health >= 50.0 {
+        red = (1.0 - health/100.0) * 2.0;
+        green = 1.0;
+    }
+    else {
+        red = 1.0 - health/100.0;
+        green = health/100.0 / 2.0;
+    }
+
+    let new_color = Color::rgb(red, green, 0.0);
+    text.sections[3].style.color = new_color;
+}
+
+fn update_serve_countdown(
+    countdown: Option<Res<ServeCountdown>>,
+    mut query: Query<&mut Text, With<ServeCountdownText>>,
+) {
+    let value = match countdown {
+        Some(countdown) => countdown.remaining_secs().ceil().to_string(),
+        None => String::new(),
+    };
+
+    for mut text in &mut query {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+fn level_cleared_setup(
+    mut commands: Commands,
+    level: Res<Level>,
+    levels: Res<Levels>,
+) {
+    let next_level = levels.layout(Level(level.0 + 1));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            Name::new("Level Cleared"),
+            OnLevelClearedScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("Level {} cleared!", level.0),
+                TextStyle {
+                    font_size: LEVEL_CLEARED_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                format!("Up next: {}", next_level.name),
+                TextStyle {
+                    font_size: NEXT_LEVEL_FONT_SIZE,
+                    color: LEVEL_COLOR,
+                    ..default()
+                },
+            ));
+            // Show the seed so players can share a game with the same generated levels
+            parent.spawn(TextBundle::from_section(
+                format!("Seed: {}", levels.seed),
+                TextStyle {
+                    font_size: NEXT_LEVEL_FONT_SIZE,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+        });
+}
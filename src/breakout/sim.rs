@@ -0,0 +1,1075 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::block::{damage_blocks, Block, BlockHit};
+use super::collision::sweep_circle_aabb;
+use super::events::{
+    BallHitBlock, BallHitPaddle, BallHitWall, BlockDestroyed, HealthChanged, LevelCleared,
+    LivesChanged,
+};
+use super::layout::LevelLayout;
+use super::level::{advance_level, level_cleared_setup, Level, LevelSeed, Levels};
+use super::power_up::{
+    clear_power_ups, collect_power_ups, drop_power_ups, power_up_setup, tick_power_ups,
+    ActivePowerUps, PowerUpModifiers, PowerUpRng,
+};
+use super::rng::SeededRng;
+use crate::{despawn_screen, GameState};
+
+// Paddle constants
+pub const PADDLE_SIZE: Vec3 = Vec3::new(120.0, 20.0, 0.0);
+const PADDLE_SPEED: f32 = 500.0;
+const GAP_BETWEEN_PADDLE_AND_FLOOR: f32 = 60.0;
+pub(super) const PADDLE_PADDING: f32 = 10.0; // How close paddle can get to the wall
+pub const PADDLE_Y: f32 = BOTTOM_WALL + GAP_BETWEEN_PADDLE_AND_FLOOR;
+
+// Give a z value to the ball so it stays on top
+const BALL_Z: f32 = 1.0;
+pub const BALL_SIZE: Vec3 = Vec3::new(30.0, 30.0, 0.0);
+// A served ball waits on the paddle for this long before it launches by itself
+const AUTO_LAUNCH_SECONDS: f32 = 3.0;
+// How fast the aim of a waiting ball sweeps from side to side, in sweeps per second
+const AIM_SWEEP_SPEED: f32 = 0.5;
+// The ball speeds up in steps: one step for every so many blocks broken, and one more every so
+// often, until it reaches the top speed
+const BALL_SPEED: f32 = 400.0;
+const BALL_SPEED_STEP: f32 = 50.0;
+pub const MAX_BALL_SPEED_TIER: u32 = 8;
+const BLOCKS_PER_SPEED_TIER: u32 = 10;
+const SECONDS_PER_SPEED_TIER: f32 = 30.0;
+// A corner can bounce the ball back and forth, so give up after this many bounces in one tick
+const MAX_BOUNCES_PER_TICK: usize = 4;
+// Default rebound angles off the paddle, in degrees above the horizontal. Short of straight up,
+// so a ball off the middle of the paddle doesn't just bounce up and down between the same blocks.
+const PADDLE_BOUNCE_MIN_ANGLE: f32 = 30.0;
+const PADDLE_BOUNCE_MAX_ANGLE: f32 = 75.0;
+// However the angles are set, the ball never leaves the paddle flatter or steeper than this
+const PADDLE_BOUNCE_LOWEST_ANGLE: f32 = 15.0;
+const PADDLE_BOUNCE_HIGHEST_ANGLE: f32 = 80.0;
+// How far the ball is kept from whatever it bounced off
+const CONTACT_OFFSET: f32 = 0.01;
+
+// Set up wall constants
+pub const LEFT_WALL: f32 = -450.0;
+pub const RIGHT_WALL: f32 = 450.0;
+pub const TOP_WALL: f32 = 300.0;
+pub const BOTTOM_WALL: f32 = -300.0;
+pub const WALL_THICKNESS: f32 = 10.0;
+
+// Set up blocks
+pub const BLOCK_SIZE: Vec2 = Vec2::new(100.0, 30.0);
+
+// Scoreboard
+const HEALTH_DECREMENT: usize = 5;
+pub const STARTING_HEALTH: usize = 100;
+pub const STARTING_LIVES: usize = 3;
+// How long it takes to serve a new ball after losing one
+const SERVE_COUNTDOWN_SECONDS: f32 = 3.0;
+
+// This plugin holds all of the breakout rules: spawning the ball, paddle, walls and blocks,
+// moving them, resolving collisions and keeping score. It only works with transforms and
+// plain components, so it runs just as well under `MinimalPlugins` as it does in a window.
+// Visuals are attached by `BreakoutRenderPlugin` and the paddle is driven through the
+// `PaddleInput` resource, which `BreakoutInputPlugin` fills in from the keyboard.
+// The blocks are spawned from the layouts in the `Levels` resource, which defaults to just
+// the full grid. Clearing a level moves on to the next one through `GameState::LevelCleared`.
+// Broken blocks sometimes drop power-up capsules, see `power_up` for the effects.
+// How a game is lost depends on the `GameMode` resource. Every ball is served from the paddle
+// and waits there until `PaddleInput::launch` is set or it gets tired of waiting.
+// Everything that happens along the way is sent out as one of the events in `events`.
+//
+// The app is expected to have declared `GameState` with `add_state` before this plugin runs.
+pub struct BreakoutSimPlugin;
+
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States, Reflect)]
+pub enum PausedState {
+    #[default]
+    Nil,
+    Running,
+    Paused,
+}
+
+impl Plugin for BreakoutSimPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // Declare the paused state
+            .add_state::<PausedState>()
+            .register_type::<PausedState>()
+            .register_type::<Scoreboard>()
+            .register_type::<Level>()
+            .register_type::<Block>()
+            .register_type::<PowerUpModifiers>()
+            .register_type::<PaddleBounce>()
+            .register_type::<BallSpeed>()
+            .register_type::<GameMode>()
+            .add_event::<BlockHit>()
+            .add_event::<BallHitPaddle>()
+            .add_event::<BallHitWall>()
+            .add_event::<BallHitBlock>()
+            .add_event::<BlockDestroyed>()
+            .add_event::<HealthChanged>()
+            .add_event::<LivesChanged>()
+            .add_event::<LevelCleared>()
+            .insert_resource(Scoreboard {
+                score: 0,
+                health: STARTING_HEALTH,
+                lives: STARTING_LIVES,
+            })
+            .init_resource::<GameMode>()
+            .init_resource::<PaddleInput>()
+            .init_resource::<PaddleBounce>()
+            .init_resource::<BallSpeed>()
+            .init_resource::<Level>()
+            .init_resource::<Levels>()
+            .init_resource::<LevelSeed>()
+            .init_resource::<ActivePowerUps>()
+            .init_resource::<PowerUpModifiers>()
+            .init_resource::<PowerUpRng>()
+            // Restarting from the pause screen goes straight to a new game, so whatever is left
+            // of the old one is cleared first
+            .add_systems(
+                OnEnter(GameState::NewGame),
+                (despawn_screen::<OnGameScreen>, setup, power_up_setup).chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_velocity,
+                    move_paddle,
+                    launch_balls,
+                    dock_balls,
+                    apply_ball_speed,
+                    check_collisions,
+                    // Before `damage_blocks`, so a bomb goes off in the same tick
+                    collect_power_ups,
+                    damage_blocks,
+                    raise_ball_speed,
+                    drop_power_ups,
+                    tick_power_ups,
+                    check_blocks,
+                    check_lost_balls,
+                    serve_ball,
+                    check_game_over,
+                ).chain()
+                    .run_if(in_state(PausedState::Running))
+                    .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(OnEnter(GameState::LevelCleared), (clear_power_ups, level_cleared_setup))
+            .add_systems(
+                Update,
+                advance_level
+                    .run_if(in_state(PausedState::Running))
+                    .run_if(in_state(GameState::LevelCleared))
+            )
+            // The run is over once the game is lost or quit from the pause screen
+            .add_systems(OnEnter(PausedState::Nil), despawn_screen::<OnGameScreen>);
+    }
+}
+
+// Tag component used to tag entities added on the game screen
+#[derive(Component)]
+pub struct OnGameScreen;
+
+#[derive(Component)]
+pub struct Ball;
+
+#[derive(Component)]
+pub struct Paddle;
+
+// A ball sitting on the paddle, waiting to be launched. Its aim sweeps from side to side, and it
+// is launched in whatever direction it is aiming at the time.
+#[derive(Component, Debug)]
+pub struct Docked {
+    // How long the ball has been waiting, which also drives the aim
+    pub waited: f32,
+}
+
+impl Docked {
+    // Where the aim is right now, from -1.0 (far left) to 1.0 (far right), matching the offset
+    // used by `PaddleBounce::direction`
+    pub fn aim(&self) -> f32 {
+        (self.waited * AIM_SWEEP_SPEED * std::f32::consts::TAU).sin()
+    }
+
+    pub fn direction(&self, paddle_bounce: &PaddleBounce) -> Vec2 {
+        paddle_bounce.direction(self.aim())
+    }
+}
+
+// The color a block was given by its level
+#[derive(Component, Deref)]
+pub struct BlockColor(pub Color);
+
+#[derive(Component, Deref, DerefMut)]
+pub struct Velocity(pub Vec2);
+
+#[derive(Component)]
+pub struct Collider;
+
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct Scoreboard {
+    pub score: usize,
+    pub health: usize,
+    pub lives: usize,
+}
+
+// The two ways to play. In `Health` mode the ball bounces off the bottom wall and every bounce
+// costs some health. In `Lives` mode the ball falls out of play, which costs a life, and a new
+// one is served from the paddle after a short countdown.
+#[derive(Resource, Component, Reflect, Default, Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub enum GameMode {
+    #[default]
+    Health,
+    Lives,
+}
+
+// Counts down to serving a new ball after the last one was lost
+#[derive(Resource, Deref, DerefMut)]
+pub struct ServeCountdown(pub Timer);
+
+// How fast the ball is going. Every ball always moves at `speed()`, only its direction changes
+// when it bounces. The tier goes up as blocks break and as time passes, up to
+// `MAX_BALL_SPEED_TIER`, and starts back at 1 with every new game.
+#[derive(Resource, Reflect, Debug, Clone)]
+#[reflect(Resource)]
+pub struct BallSpeed {
+    // Starts at 1
+    pub tier: u32,
+    // Blocks broken towards the next tier
+    pub blocks_broken: u32,
+    pub timer: Timer,
+}
+
+impl Default for BallSpeed {
+    fn default() -> Self {
+        BallSpeed {
+            tier: 1,
+            blocks_broken: 0,
+            timer: Timer::from_seconds(SECONDS_PER_SPEED_TIER, TimerMode::Repeating),
+        }
+    }
+}
+
+impl BallSpeed {
+    pub fn speed(&self) -> f32 {
+        BALL_SPEED + (self.tier.clamp(1, MAX_BALL_SPEED_TIER) - 1) as f32 * BALL_SPEED_STEP
+    }
+
+    pub fn is_max(&self) -> bool {
+        self.tier >= MAX_BALL_SPEED_TIER
+    }
+
+    fn raise(&mut self) {
+        self.tier = (self.tier + 1).min(MAX_BALL_SPEED_TIER);
+    }
+}
+
+// How the paddle should move this tick. -1.0 is full speed to the left and 1.0 is full speed
+// to the right. Whatever is controlling the paddle (keyboard, a bot, a test) writes here.
+// A pointer sets `target_x` instead, and the paddle goes straight to that x position (as far
+// as the walls let it), ignoring `direction`.
+// `launch` asks to launch the balls waiting on the paddle. It stays set until the next tick
+// picks it up, so a quick tap isn't missed when no tick runs that frame.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct PaddleInput {
+    pub direction: f32,
+    pub target_x: Option<f32>,
+    pub launch: bool,
+}
+
+// Where the ball goes when it bounces off the top of the paddle. Hitting the middle sends it
+// out at `max_angle` and hitting the very edge at `min_angle`, towards the side that was hit.
+// Both angles are in degrees above the horizontal, and the ball keeps its speed either way.
+// They can still be changed through reflection (in the inspector), so `direction` keeps them
+// within limits as well.
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+#[reflect(Resource)]
+pub struct PaddleBounce {
+    min_angle: f32,
+    max_angle: f32,
+}
+
+impl Default for PaddleBounce {
+    fn default() -> Self {
+        PaddleBounce::new(PADDLE_BOUNCE_MIN_ANGLE, PADDLE_BOUNCE_MAX_ANGLE)
+    }
+}
+
+impl PaddleBounce {
+    // The ball has to leave the paddle upwards and to the side that was hit, so both angles are
+    // well above the horizontal and short of straight up, and the edges send it out lower than
+    // the middle
+    pub fn new(min_angle: f32, max_angle: f32) -> Self {
+        assert!(
+            PADDLE_BOUNCE_LOWEST_ANGLE <= min_angle
+                && min_angle < max_angle
+                && max_angle <= PADDLE_BOUNCE_HIGHEST_ANGLE,
+            "paddle bounce angles must be {PADDLE_BOUNCE_LOWEST_ANGLE} <= min ({min_angle}) \
+             < max ({max_angle}) <= {PADDLE_BOUNCE_HIGHEST_ANGLE}",
+        );
+
+        PaddleBounce { min_angle, max_angle }
+    }
+
+    pub fn min_angle(&self) -> f32 {
+        self.min_angle
+    }
+
+    pub fn max_angle(&self) -> f32 {
+        self.max_angle
+    }
+
+    // Direction the ball leaves in when it hits `offset` from the middle of the paddle, where
+    // -1.0 is the left edge and 1.0 the right edge
+    pub fn direction(&self, offset: f32) -> Vec2 {
+        let offset = offset.clamp(-1.0, 1.0);
+        let within_limits =
+            |angle: f32| angle.clamp(PADDLE_BOUNCE_LOWEST_ANGLE, PADDLE_BOUNCE_HIGHEST_ANGLE);
+        let (min_angle, max_angle) = (within_limits(self.min_angle), within_limits(self.max_angle));
+        let angle = max_angle - offset.abs() * (max_angle - min_angle);
+        let (sin, cos) = angle.to_radians().sin_cos();
+
+        Vec2::new(cos * offset.signum(), sin)
+    }
+}
+
+#[derive(Component)]
+pub struct Wall {
+    pub location: WallLocation,
+}
+
+// We will have four walls
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum WallLocation {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl WallLocation {
+    // Get the position of each wall
+    fn position(&self) -> Vec2 {
+        match self {
+            WallLocation::Left => Vec2::new(LEFT_WALL, 0.),
+            WallLocation::Right => Vec2::new(RIGHT_WALL, 0.),
+            WallLocation::Bottom => Vec2::new(0., BOTTOM_WALL),
+            WallLocation::Top => Vec2::new(0., TOP_WALL),
+        }
+    }
+
+    // Get the size of the area
+    fn size(&self) -> Vec2 {
+        let arena_height = TOP_WALL - BOTTOM_WALL;
+        let arena_width = RIGHT_WALL - LEFT_WALL;
+
+        // Make sure we have a non-zero arena
+        assert!(arena_height > 0.0);
+        assert!(arena_width > 0.0);
+
+        match self {
+            WallLocation::Left | WallLocation::Right => {
+                Vec2::new(WALL_THICKNESS, arena_height + WALL_THICKNESS)
+            }
+            WallLocation::Top | WallLocation::Bottom => {
+                Vec2::new(arena_width + WALL_THICKNESS, WALL_THICKNESS)
+            }
+        }
+    }
+}
+
+#[derive(Bundle)]
+struct WallBundle {
+    // We need a transform and a collider for each wall, the sprite is added by the renderer
+    transform_bundle: TransformBundle,
+    wall: Wall,
+    collider: Collider,
+}
+
+impl WallBundle {
+    fn new(location: WallLocation) -> WallBundle {
+        WallBundle {
+            transform_bundle: TransformBundle::from_transform(Transform {
+                // We need to convert Vec2 to Vec3 to give it a z-ccoordinate
+                // which is used to determine the order of sprites
+                translation: location.position().extend(0.0),
+                // The z-scale of 2d objects needs to be 1.0 or
+                // the ordering will be affected
+                scale: location.size().extend(1.0),
+                ..default()
+            }),
+            wall: Wall {
+                location,
+            },
+            collider: Collider,
+        }
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut ball_speed: ResMut<BallSpeed>,
+    mut level: ResMut<Level>,
+    mut levels: ResMut<Levels>,
+    level_seed: Res<LevelSeed>,
+    time: Res<Time<Real>>,
+) {
+    // Ensure the scoreboard and level are reset with each new game
+    scoreboard.score = 0;
+    scoreboard.health = STARTING_HEALTH;
+    scoreboard.lives = STARTING_LIVES;
+    commands.remove_resource::<ServeCountdown>();
+    *ball_speed = BallSpeed::default();
+    *level = Level::default();
+
+    // Pick the seed for any generated levels in this game
+    levels.seed = match *level_seed {
+        LevelSeed::Fixed(seed) => seed,
+        LevelSeed::Random => SeededRng::new(time.elapsed().as_nanos() as u64).next_u64(),
+    };
+    info!("Level seed for this game: {}", levels.seed);
+    let layout = levels.layout(*level);
+
+    // Create the Paddle, with the ball waiting on it
+    let mut paddle_transform = Transform::default();
+    reset_paddle(&mut paddle_transform, &layout);
+    spawn_docked_ball(&mut commands, paddle_transform.translation.x);
+    commands.spawn((
+        TransformBundle::from_transform(paddle_transform),
+        Paddle,
+        Collider,
+        Name::new("Paddle"),
+        OnGameScreen,
+    ));
+
+    // Create the walls
+    commands.spawn((WallBundle::new(WallLocation::Left),
+        Name::new("Left Wall"),
+        OnGameScreen,));
+    commands.spawn((WallBundle::new(WallLocation::Right),
+        Name::new("Right Wall"),
+        OnGameScreen,));
+    commands.spawn((WallBundle::new(WallLocation::Top),
+        Name::new("Top Wall"),
+        OnGameScreen,));
+    commands.spawn((WallBundle::new(WallLocation::Bottom),
+        Name::new("Bottom Wall"),
+        OnGameScreen,));
+
+    // Generate all the blocks
+    generate_blocks(&mut commands, &layout);
+
+    // Finally, transition to the running game state (NewGame is just for starting a brand new
+    // game)
+    game_state.set(GameState::InGame);
+}
+
+// Where a ball waiting on the paddle sits, right on top of it
+fn docked_position(paddle_x: f32) -> Vec3 {
+    Vec3::new(paddle_x, PADDLE_Y + PADDLE_SIZE.y / 2.0 + BALL_SIZE.y / 2.0 + CONTACT_OFFSET, BALL_Z)
+}
+
+// Spawn a ball in play. There can be any number of them at once.
+pub(super) fn spawn_ball(commands: &mut Commands, translation: Vec3, velocity: Vec2) -> Entity {
+    commands.spawn((
+        TransformBundle::from_transform(
+            Transform::from_translation(translation).with_scale(BALL_SIZE)
+        ),
+        Ball,
+        Velocity(velocity),
+        Name::new("Ball"),
+        OnGameScreen,
+    )).id()
+}
+
+// Serve a new ball on top of the paddle
+pub(super) fn spawn_docked_ball(commands: &mut Commands, paddle_x: f32) {
+    let ball = spawn_ball(commands, docked_position(paddle_x), Vec2::Y * BALL_SPEED);
+    commands.entity(ball).insert(Docked { waited: 0.0 });
+}
+
+// Center the paddle and give it the width the level asks for
+pub(super) fn reset_paddle(transform: &mut Transform, layout: &LevelLayout) {
+    *transform = Transform {
+        translation: Vec3::new(0.0, PADDLE_Y, 0.0),
+        scale: Vec3::new(layout.paddle_width, PADDLE_SIZE.y, PADDLE_SIZE.z),
+        ..default()
+    };
+}
+
+// Everything but the ball, which `check_collisions` moves itself
+fn apply_velocity(
+    mut query: Query<(&mut Transform, &Velocity), Without<Ball>>,
+    time: Res<Time>
+) {
+    for (mut transform, velocity) in &mut query {
+        transform.translation.x += velocity.x * time.delta_seconds();
+        transform.translation.y += velocity.y * time.delta_seconds();
+    }
+}
+
+// Launch the balls waiting on the paddle when asked to, or when they've waited long enough
+fn launch_balls(
+    mut commands: Commands,
+    mut paddle_input: ResMut<PaddleInput>,
+    paddle_bounce: Res<PaddleBounce>,
+    mut ball_query: Query<(Entity, &Docked, &mut Velocity), With<Ball>>,
+) {
+    for (entity, docked, mut velocity) in &mut ball_query {
+        if paddle_input.launch || docked.waited >= AUTO_LAUNCH_SECONDS {
+            velocity.0 = docked.direction(&paddle_bounce) * BALL_SPEED;
+            commands.entity(entity).remove::<Docked>();
+        }
+    }
+
+    paddle_input.launch = false;
+}
+
+// Balls waiting on the paddle move along with it
+fn dock_balls(
+    mut ball_query: Query<(&mut Transform, &mut Docked), (With<Ball>, Without<Paddle>)>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    time: Res<Time>,
+) {
+    let paddle_transform = paddle_query.single();
+
+    for (mut transform, mut docked) in &mut ball_query {
+        docked.waited += time.delta_seconds();
+        transform.translation = docked_position(paddle_transform.translation.x);
+    }
+}
+
+// Keep every ball at the current speed, without changing where it is heading
+fn apply_ball_speed(
+    ball_speed: Res<BallSpeed>,
+    modifiers: Res<PowerUpModifiers>,
+    mut ball_query: Query<&mut Velocity, With<Ball>>,
+) {
+    let speed = ball_speed.speed() * modifiers.ball_speed;
+
+    for mut velocity in &mut ball_query {
+        velocity.0 = velocity.normalize_or_zero() * speed;
+    }
+}
+
+// Go up a speed tier for every `BLOCKS_PER_SPEED_TIER` blocks broken, and every
+// `SECONDS_PER_SPEED_TIER` seconds. The two count separately, so a tier from the timer doesn't
+// throw away the blocks broken so far, and an explosion that breaks a lot of blocks at once can
+// go up more than one tier.
+fn raise_ball_speed(
+    mut ball_speed: ResMut<BallSpeed>,
+    mut destroyed_blocks: EventReader<BlockDestroyed>,
+    time: Res<Time>,
+) {
+    ball_speed.blocks_broken += destroyed_blocks.read().count() as u32;
+    while ball_speed.blocks_broken >= BLOCKS_PER_SPEED_TIER {
+        ball_speed.blocks_broken -= BLOCKS_PER_SPEED_TIER;
+        ball_speed.raise();
+    }
+
+    if ball_speed.timer.tick(time.delta()).just_finished() {
+        ball_speed.raise();
+    }
+}
+
+// Move the ball and bounce it off anything in its way. The ball is swept along its whole path
+// for the tick instead of being checked where it ends up, so it can't tunnel through walls or
+// blocks however fast it goes, and it can bounce more than once in a single tick.
+fn check_collisions(
+    mut scoreboard: ResMut<Scoreboard>,
+    paddle_bounce: Res<PaddleBounce>,
+    game_mode: Res<GameMode>,
+    mut ball_query: Query<(Entity, &mut Velocity, &mut Transform), (With<Ball>, Without<Docked>)>,
+    collider_query: Query<
+        (Entity, &Transform, Option<&Block>, Option<&Wall>, Has<Paddle>),
+        (With<Collider>, Without<Ball>),
+    >,
+    mut paddle_hits: EventWriter<BallHitPaddle>,
+    mut wall_hits: EventWriter<BallHitWall>,
+    mut ball_block_hits: EventWriter<BallHitBlock>,
+    mut block_hits: EventWriter<BlockHit>,
+    mut health_changes: EventWriter<HealthChanged>,
+    time: Res<Time>,
+) {
+    // Balls waiting on the paddle aren't in play yet
+    for (ball, mut ball_velocity, mut ball_transform) in &mut ball_query {
+        let radius = ball_transform.scale.x / 2.0;
+        let mut remaining = time.delta_seconds();
+
+        for _ in 0..MAX_BOUNCES_PER_TICK {
+            let position = ball_transform.translation.truncate();
+            let motion = ball_velocity.0 * remaining;
+
+            // Find the first thing the ball runs into
+            let first_hit = collider_query
+                .iter()
+                // Without a bottom wall to bounce off, the ball can fall out of play
+                .filter(|(_, _, _, maybe_wall, _)| {
+                    *game_mode == GameMode::Health
+                        || !maybe_wall.is_some_and(|wall| wall.location == WallLocation::Bottom)
+                })
+                .filter_map(|collider| {
+                    let (_, transform, ..) = collider;
+                    sweep_circle_aabb(
+                        position,
+                        motion,
+                        radius,
+                        transform.translation.truncate(),
+                        transform.scale.truncate() / 2.0,
+                    ).map(|hit| (hit, collider))
+                })
+                .min_by(|a, b| a.0.time.total_cmp(&b.0.time));
+
+            let Some((hit, (collider_entity, collider_transform, maybe_block, maybe_wall, is_paddle))) = first_hit else {
+                // Nothing in the way, so the ball can travel the rest of the tick
+                ball_transform.translation += motion.extend(0.0);
+                break;
+            };
+
+            // Move up to the point of contact, just far enough off the surface to not touch it
+            ball_transform.translation += (motion * hit.time + hit.normal * CONTACT_OFFSET).extend(0.0);
+            remaining *= 1.0 - hit.time;
+
+            let position = ball_transform.translation.truncate();
+
+            // Decrease health if the ball hits the bottom wall
+            if maybe_wall.is_some_and(|wall| wall.location == WallLocation::Bottom) {
+                let previous = scoreboard.health;
+                scoreboard.health = previous.saturating_sub(HEALTH_DECREMENT);
+                if scoreboard.health != previous {
+                    health_changes.send(HealthChanged { previous, current: scoreboard.health });
+                }
+            }
+
+            if is_paddle && hit.normal.y > 0.0 {
+                // Off the top of the paddle, the ball goes where the player aimed it
+                let half_width = collider_transform.scale.x / 2.0 + radius;
+                let offset =
+                    (ball_transform.translation.x - collider_transform.translation.x) / half_width;
+                ball_velocity.0 = paddle_bounce.direction(offset) * ball_velocity.length();
+            } else {
+                // Reflect the ball off the surface it hit
+                let velocity_into_surface = ball_velocity.dot(hit.normal);
+                ball_velocity.0 -= 2.0 * velocity_into_surface * hit.normal;
+            }
+
+            // Let everyone else know what the ball hit
+            if is_paddle {
+                paddle_hits.send(BallHitPaddle { ball, position, speed: ball_velocity.length() });
+            } else if let Some(wall) = maybe_wall {
+                wall_hits.send(BallHitWall { ball, wall: wall.location, position });
+            } else if maybe_block.is_some() {
+                ball_block_hits.send(BallHitBlock { ball, block: collider_entity, position });
+                // Blocks take damage when hit, and break once they run out of hit points
+                block_hits.send(BlockHit(collider_entity));
+            }
+        }
+
+        // If the ball bounced around a corner more times than we check in a tick, it just waits
+        // there for the next one
+    }
+}
+
+fn check_blocks(
+    block_query: Query<&Block>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if block_query.iter().all(|block| !block.kind.is_breakable()) {
+        // If we have destroyed all blocks (steel ones don't count), the level is cleared
+        game_state.set(GameState::LevelCleared);
+    }
+}
+
+// In lives mode, a ball that falls past the bottom wall is gone. Losing the last ball in play
+// costs a life.
+fn check_lost_balls(
+    mut commands: Commands,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut lives_changes: EventWriter<LivesChanged>,
+    game_mode: Res<GameMode>,
+    ball_query: Query<(Entity, &Transform), With<Ball>>,
+) {
+    if *game_mode != GameMode::Lives {
+        return;
+    }
+
+    let mut balls_left = 0;
+    let mut balls_lost = 0;
+    for (entity, transform) in &ball_query {
+        if transform.translation.y < BOTTOM_WALL - transform.scale.y / 2.0 {
+            commands.entity(entity).despawn();
+            balls_lost += 1;
+        } else {
+            balls_left += 1;
+        }
+    }
+
+    if balls_lost > 0 && balls_left == 0 {
+        let previous = scoreboard.lives;
+        scoreboard.lives = previous.saturating_sub(1);
+        lives_changes.send(LivesChanged { previous, current: scoreboard.lives });
+
+        if scoreboard.lives > 0 {
+            commands.insert_resource(ServeCountdown(
+                Timer::from_seconds(SERVE_COUNTDOWN_SECONDS, TimerMode::Once)
+            ));
+        }
+    }
+}
+
+// Once the countdown is over, serve a new ball on the paddle
+fn serve_ball(
+    mut commands: Commands,
+    countdown: Option<ResMut<ServeCountdown>>,
+    paddle_query: Query<&Transform, With<Paddle>>,
+    time: Res<Time>,
+) {
+    let Some(mut countdown) = countdown else {
+        return;
+    };
+    if !countdown.tick(time.delta()).finished() {
+        return;
+    }
+    commands.remove_resource::<ServeCountdown>();
+
+    spawn_docked_ball(&mut commands, paddle_query.single().translation.x);
+}
+
+fn check_game_over(
+    scoreboard: Res<Scoreboard>,
+    game_mode: Res<GameMode>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut paused_state: ResMut<NextState<PausedState>>,
+) {
+    let out_of_play = match *game_mode {
+        GameMode::Health => scoreboard.health == 0,
+        GameMode::Lives => scoreboard.lives == 0,
+    };
+
+    if out_of_play {
+        // Game over!
+        paused_state.set(PausedState::Nil);
+        game_state.set(GameState::GameOver);
+    }
+}
+
+fn move_paddle(
+    mut paddle_query: Query<&mut Transform, With<Paddle>>,
+    paddle_input: Res<PaddleInput>,
+    modifiers: Res<PowerUpModifiers>,
+    time: Res<Time>,
+) {
+    let mut paddle_transform = paddle_query.single_mut();
+    let direction = paddle_input.direction.clamp(-1.0, 1.0);
+
+    let new_paddle_position = paddle_input.target_x.unwrap_or(
+        paddle_transform.translation.x + direction * PADDLE_SPEED * modifiers.paddle_speed * time.delta_seconds()
+    );
+
+    // Make sure paddle stops before each wall
+    let paddle_width = paddle_transform.scale.x;
+    let left_bound = LEFT_WALL + WALL_THICKNESS / 2.0 + paddle_width / 2.0 + PADDLE_PADDING;
+    let right_bound = RIGHT_WALL - WALL_THICKNESS / 2.0 - paddle_width / 2.0 - PADDLE_PADDING;
+
+    paddle_transform.translation.x = new_paddle_position.clamp(left_bound, right_bound);
+}
+
+pub(super) fn generate_blocks(commands: &mut Commands, layout: &LevelLayout) {
+    for block in &layout.blocks {
+        // Create the block
+        commands.spawn((
+            TransformBundle::from_transform(Transform {
+                translation: block.position.extend(0.0),
+                scale: Vec3::new(BLOCK_SIZE.x, BLOCK_SIZE.y, 1.0),
+                ..default()
+            }),
+            Block::new(block.kind),
+            BlockColor(block.color),
+            Collider,
+            Name::new("Block"),
+            OnGameScreen,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{ecs::system::RunSystemOnce, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::breakout::block::BlockKind;
+    use crate::breakout::power_up::{PowerUp, PowerUpCapsule, CAPSULE_SIZE};
+
+    // One tick of the default 64Hz fixed timestep
+    const TICK: Duration = Duration::from_micros(15_625);
+
+    // Everything `check_collisions` needs, with the clock one tick along
+    fn collision_world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Scoreboard>();
+        world.init_resource::<PaddleBounce>();
+        world.init_resource::<GameMode>();
+        world.init_resource::<Events<BallHitPaddle>>();
+        world.init_resource::<Events<BallHitWall>>();
+        world.init_resource::<Events<BallHitBlock>>();
+        world.init_resource::<Events<BlockHit>>();
+        world.init_resource::<Events<HealthChanged>>();
+
+        let mut time = Time::<()>::default();
+        time.advance_by(TICK);
+        world.insert_resource(time);
+        world
+    }
+
+    fn spawn_test_ball(world: &mut World, translation: Vec2, velocity: Vec2) -> Entity {
+        world.spawn((
+            TransformBundle::from_transform(
+                Transform::from_translation(translation.extend(BALL_Z)).with_scale(BALL_SIZE)
+            ),
+            Ball,
+            Velocity(velocity),
+        )).id()
+    }
+
+    fn ball_after_tick(world: &mut World, ball: Entity) -> (Vec2, Vec2) {
+        world.run_system_once(check_collisions);
+        let position = world.get::<Transform>(ball).unwrap().translation.truncate();
+        let velocity = world.get::<Velocity>(ball).unwrap().0;
+        (position, velocity)
+    }
+
+    #[test]
+    fn paddle_never_bounces_ball_straight_up() {
+        let paddle_bounce = PaddleBounce::default();
+        for offset in [-1.0, -0.5, -0.01, 0.0, 0.01, 0.5, 1.0] {
+            let direction = paddle_bounce.direction(offset);
+            assert!(direction.x != 0.0 && direction.y > 0.0, "{offset} goes {direction}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn paddle_bounce_rejects_vertical_angle() {
+        PaddleBounce::new(30.0, 90.0);
+    }
+
+    #[test]
+    fn paddle_bounce_keeps_edited_angles_within_limits() {
+        // Like changing the resource in the inspector, which doesn't go through `new`
+        let paddle_bounce = PaddleBounce { min_angle: 0.0, max_angle: 90.0 };
+
+        let middle = paddle_bounce.direction(0.0);
+        assert!((middle.y.asin().to_degrees() - PADDLE_BOUNCE_HIGHEST_ANGLE).abs() < 1e-3);
+        let edge = paddle_bounce.direction(-1.0);
+        assert!(edge.x < 0.0);
+        assert!((edge.y.asin().to_degrees() - PADDLE_BOUNCE_LOWEST_ANGLE).abs() < 1e-3);
+    }
+
+    fn ball_speed_after(ball_speed: BallSpeed, blocks_broken: usize, elapsed: Duration) -> BallSpeed {
+        let mut world = World::new();
+        world.insert_resource(ball_speed);
+        let mut destroyed_blocks = Events::<BlockDestroyed>::default();
+        for _ in 0..blocks_broken {
+            destroyed_blocks.send(BlockDestroyed {
+                entity: Entity::PLACEHOLDER,
+                kind: BlockKind::default(),
+                position: Vec3::ZERO,
+                score: 1,
+                color: Color::WHITE,
+            });
+        }
+        world.insert_resource(destroyed_blocks);
+        let mut time = Time::<()>::default();
+        time.advance_by(elapsed);
+        world.insert_resource(time);
+
+        world.run_system_once(raise_ball_speed);
+        world.remove_resource::<BallSpeed>().unwrap()
+    }
+
+    #[test]
+    fn ball_speed_rises_a_tier_per_blocks_broken() {
+        let ball_speed = ball_speed_after(BallSpeed::default(), 25, TICK);
+        assert_eq!(ball_speed.tier, 3);
+        assert_eq!(ball_speed.blocks_broken, 5);
+    }
+
+    #[test]
+    fn timer_keeps_blocks_broken() {
+        let ball_speed = BallSpeed { blocks_broken: 7, ..default() };
+        let ball_speed =
+            ball_speed_after(ball_speed, 0, Duration::from_secs_f32(SECONDS_PER_SPEED_TIER));
+        assert_eq!(ball_speed.tier, 2);
+        assert_eq!(ball_speed.blocks_broken, 7);
+    }
+
+    #[test]
+    fn fast_ball_bounces_off_wall() {
+        let mut world = collision_world();
+        world.spawn(WallBundle::new(WallLocation::Right));
+        // Fast enough to cross the whole 10px wall in one tick
+        let ball = spawn_test_ball(&mut world, Vec2::new(400.0, 0.0), Vec2::new(10_000.0, 0.0));
+
+        let (position, velocity) = ball_after_tick(&mut world, ball);
+
+        assert!(position.x <= RIGHT_WALL - WALL_THICKNESS / 2.0 - BALL_SIZE.x / 2.0);
+        assert_eq!(velocity, Vec2::new(-10_000.0, 0.0));
+        assert_eq!(world.resource::<Events<BallHitWall>>().len(), 1);
+    }
+
+    #[test]
+    fn fast_ball_bounces_off_block() {
+        let mut world = collision_world();
+        let block = world.spawn((
+            TransformBundle::from_transform(
+                Transform::from_xyz(0.0, 100.0, 0.0).with_scale(BLOCK_SIZE.extend(1.0))
+            ),
+            Block::new(BlockKind::default()),
+            Collider,
+        )).id();
+        let ball = spawn_test_ball(&mut world, Vec2::ZERO, Vec2::new(0.0, 10_000.0));
+
+        let (position, velocity) = ball_after_tick(&mut world, ball);
+
+        assert!(position.y <= 100.0 - BLOCK_SIZE.y / 2.0 - BALL_SIZE.y / 2.0);
+        assert_eq!(velocity, Vec2::new(0.0, -10_000.0));
+        let block_hits = world.resource::<Events<BlockHit>>();
+        assert_eq!(block_hits.len(), 1);
+        assert_eq!(block_hits.iter_current_update_events().next().unwrap().0, block);
+    }
+
+    #[test]
+    fn ball_bounces_out_of_corner_in_one_tick() {
+        let mut world = collision_world();
+        world.spawn(WallBundle::new(WallLocation::Right));
+        world.spawn(WallBundle::new(WallLocation::Top));
+        let ball =
+            spawn_test_ball(&mut world, Vec2::new(400.0, 240.0), Vec2::new(4_000.0, 4_000.0));
+
+        let (position, velocity) = ball_after_tick(&mut world, ball);
+
+        // Off both walls and back out the way it came
+        assert_eq!(world.resource::<Events<BallHitWall>>().len(), 2);
+        assert_eq!(velocity, Vec2::new(-4_000.0, -4_000.0));
+        assert!(position.x < RIGHT_WALL - WALL_THICKNESS / 2.0 - BALL_SIZE.x / 2.0);
+        assert!(position.y < TOP_WALL - WALL_THICKNESS / 2.0 - BALL_SIZE.y / 2.0);
+    }
+
+    #[test]
+    fn bounces_per_tick_are_capped() {
+        let mut world = collision_world();
+        world.spawn(WallBundle::new(WallLocation::Left));
+        world.spawn(WallBundle::new(WallLocation::Right));
+        // Fast enough to cross the arena many times over in one tick
+        let ball = spawn_test_ball(&mut world, Vec2::ZERO, Vec2::new(1_000_000.0, 0.0));
+
+        let (position, velocity) = ball_after_tick(&mut world, ball);
+
+        assert_eq!(world.resource::<Events<BallHitWall>>().len(), MAX_BOUNCES_PER_TICK);
+        assert_eq!(velocity.length(), 1_000_000.0);
+        // It stops at its last bounce instead of going through a wall
+        let inner_half_width = RIGHT_WALL - WALL_THICKNESS / 2.0 - BALL_SIZE.x / 2.0;
+        assert!(position.x.abs() <= inner_half_width);
+    }
+
+    // The whole simulation without a window, one fixed tick per update, with the first ball
+    // already launched
+    fn game_app(game_mode: GameMode) -> App {
+        let mut app = App::new();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_state::<GameState>()
+            .add_plugins(BreakoutSimPlugin)
+            .insert_resource(LevelSeed::Fixed(1))
+            .insert_resource(game_mode)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(TICK));
+        app.world.resource_mut::<NextState<GameState>>().set(GameState::NewGame);
+        app.world.resource_mut::<NextState<PausedState>>().set(PausedState::Running);
+        app.update();
+        app.update();
+
+        app.world.resource_mut::<PaddleInput>().launch = true;
+        app.update();
+        assert_eq!(docked_balls(&mut app), 0);
+        app
+    }
+
+    fn balls(app: &mut App) -> Vec<Entity> {
+        app.world.query_filtered::<Entity, With<Ball>>().iter(&app.world).collect()
+    }
+
+    fn docked_balls(app: &mut App) -> usize {
+        app.world.query_filtered::<(), With<Docked>>().iter(&app.world).count()
+    }
+
+    // Drop a capsule right onto the paddle
+    fn catch_power_up(app: &mut App, power_up: PowerUp) {
+        let paddle = app.world.query_filtered::<&Transform, With<Paddle>>().single(&app.world);
+        let translation = paddle.translation.truncate().extend(CAPSULE_SIZE.z);
+        app.world.spawn((
+            TransformBundle::from_transform(
+                Transform::from_translation(translation).with_scale(CAPSULE_SIZE)
+            ),
+            PowerUpCapsule(power_up),
+            Velocity(Vec2::ZERO),
+        ));
+        app.update();
+    }
+
+    fn lose_ball(app: &mut App, ball: Entity) {
+        app.world.get_mut::<Transform>(ball).unwrap().translation.y = BOTTOM_WALL - 100.0;
+        app.update();
+    }
+
+    #[test]
+    fn multi_ball_adds_two_balls() {
+        let mut app = game_app(GameMode::Health);
+        assert_eq!(balls(&mut app).len(), 1);
+
+        catch_power_up(&mut app, PowerUp::MultiBall);
+
+        assert_eq!(balls(&mut app).len(), 3);
+    }
+
+    #[test]
+    fn losing_one_of_several_balls_costs_no_life() {
+        let mut app = game_app(GameMode::Lives);
+        catch_power_up(&mut app, PowerUp::MultiBall);
+
+        let ball = balls(&mut app)[0];
+        lose_ball(&mut app, ball);
+
+        assert_eq!(balls(&mut app).len(), 2);
+        assert_eq!(app.world.resource::<Scoreboard>().lives, STARTING_LIVES);
+        assert!(!app.world.contains_resource::<ServeCountdown>());
+    }
+
+    #[test]
+    fn losing_last_ball_costs_one_life_and_serves_again() {
+        let mut app = game_app(GameMode::Lives);
+
+        let ball = balls(&mut app)[0];
+        lose_ball(&mut app, ball);
+
+        assert!(balls(&mut app).is_empty());
+        assert_eq!(app.world.resource::<Scoreboard>().lives, STARTING_LIVES - 1);
+        assert!(app.world.contains_resource::<ServeCountdown>());
+
+        // A new ball waits on the paddle once the countdown is over
+        let countdown_ticks = (SERVE_COUNTDOWN_SECONDS / TICK.as_secs_f32()).ceil() as usize;
+        for _ in 0..countdown_ticks + 1 {
+            app.update();
+        }
+
+        assert_eq!(balls(&mut app).len(), 1);
+        assert_eq!(docked_balls(&mut app), 1);
+        assert_eq!(app.world.resource::<Scoreboard>().lives, STARTING_LIVES - 1);
+        assert!(!app.world.contains_resource::<ServeCountdown>());
+    }
+}
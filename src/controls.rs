@@ -0,0 +1,181 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Everything the player can do with a key. The keys come from the `KeyBindings` resource, which
+// can be changed in Settings > Controls and is saved with the other settings, so nothing else
+// should look at `KeyCode`s directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    MoveLeft,
+    MoveRight,
+    Launch,
+    Pause,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    MenuNext,
+    ConfirmMenu,
+    MenuBack,
+    // Deletes the last letter when typing a name
+    Erase,
+    ToggleInspector,
+}
+
+// Every action, in the order they are listed on the controls screen
+pub const INPUT_ACTIONS: [InputAction; 13] = [
+    InputAction::MoveLeft,
+    InputAction::MoveRight,
+    InputAction::Launch,
+    InputAction::Pause,
+    InputAction::MenuUp,
+    InputAction::MenuDown,
+    InputAction::MenuLeft,
+    InputAction::MenuRight,
+    InputAction::MenuNext,
+    InputAction::ConfirmMenu,
+    InputAction::MenuBack,
+    InputAction::Erase,
+    InputAction::ToggleInspector,
+];
+
+// Where an action is used. Two actions can share a key as long as they are never used in the
+// same place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ActionContext {
+    Game,
+    Menu,
+    Everywhere,
+}
+
+impl InputAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::MoveLeft => "Move Left",
+            InputAction::MoveRight => "Move Right",
+            InputAction::Launch => "Launch",
+            InputAction::Pause => "Pause",
+            InputAction::MenuUp => "Menu Up",
+            InputAction::MenuDown => "Menu Down",
+            InputAction::MenuLeft => "Menu Left",
+            InputAction::MenuRight => "Menu Right",
+            InputAction::MenuNext => "Next Button",
+            InputAction::ConfirmMenu => "Confirm",
+            InputAction::MenuBack => "Back",
+            InputAction::Erase => "Erase",
+            InputAction::ToggleInspector => "Inspector",
+        }
+    }
+
+    pub fn default_key(&self) -> KeyCode {
+        match self {
+            InputAction::MoveLeft => KeyCode::Left,
+            InputAction::MoveRight => KeyCode::Right,
+            InputAction::Launch => KeyCode::Space,
+            InputAction::Pause => KeyCode::Escape,
+            InputAction::MenuUp => KeyCode::Up,
+            InputAction::MenuDown => KeyCode::Down,
+            InputAction::MenuLeft => KeyCode::Left,
+            InputAction::MenuRight => KeyCode::Right,
+            InputAction::MenuNext => KeyCode::Tab,
+            InputAction::ConfirmMenu => KeyCode::Return,
+            InputAction::MenuBack => KeyCode::Escape,
+            InputAction::Erase => KeyCode::Back,
+            InputAction::ToggleInspector => KeyCode::Grave,
+        }
+    }
+
+    fn context(&self) -> ActionContext {
+        match self {
+            InputAction::MoveLeft
+            | InputAction::MoveRight
+            | InputAction::Launch
+            | InputAction::Pause => ActionContext::Game,
+            InputAction::MenuUp
+            | InputAction::MenuDown
+            | InputAction::MenuLeft
+            | InputAction::MenuRight
+            | InputAction::MenuNext
+            | InputAction::ConfirmMenu
+            | InputAction::MenuBack
+            | InputAction::Erase => ActionContext::Menu,
+            InputAction::ToggleInspector => ActionContext::Everywhere,
+        }
+    }
+
+    // Whether both actions can be looked for at the same time, so they can't share a key
+    fn overlaps(&self, other: InputAction) -> bool {
+        self.context() == ActionContext::Everywhere
+            || other.context() == ActionContext::Everywhere
+            || self.context() == other.context()
+    }
+}
+
+// The key for every action
+#[derive(Resource, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct KeyBindings(BTreeMap<InputAction, KeyCode>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings(INPUT_ACTIONS.iter().map(|action| (*action, action.default_key())).collect())
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: InputAction) -> KeyCode {
+        self.0.get(&action).copied().unwrap_or(action.default_key())
+    }
+
+    pub fn pressed(&self, action: InputAction, keys: &Input<KeyCode>) -> bool {
+        keys.pressed(self.key(action))
+    }
+
+    pub fn just_pressed(&self, action: InputAction, keys: &Input<KeyCode>) -> bool {
+        keys.just_pressed(self.key(action))
+    }
+
+    // The action that `key` can't be given to `action` because of, if any
+    pub fn conflict(&self, action: InputAction, key: KeyCode) -> Option<InputAction> {
+        INPUT_ACTIONS
+            .into_iter()
+            .find(|other| *other != action && other.overlaps(action) && self.key(*other) == key)
+    }
+
+    // Bind `action` to `key`, unless that would clash with another action, which is returned
+    pub fn rebind(&mut self, action: InputAction, key: KeyCode) -> Result<(), InputAction> {
+        match self.conflict(action, key) {
+            Some(other) => Err(other),
+            None => {
+                self.0.insert(action, key);
+                Ok(())
+            }
+        }
+    }
+
+    // Whether any two actions clash, which can only happen when bindings are set all at once,
+    // like when they are loaded
+    pub fn has_conflicts(&self) -> bool {
+        INPUT_ACTIONS
+            .into_iter()
+            .any(|action| self.conflict(action, self.key(action)).is_some())
+    }
+
+    // Bind every action in `bindings`, without checking for clashes
+    pub fn extend(&mut self, bindings: impl IntoIterator<Item = (InputAction, KeyCode)>) {
+        self.0.extend(bindings);
+    }
+}
+
+// Like bevy's `input_toggle_active`, but for the key bound to `action`
+pub fn action_toggle_active(
+    default: bool,
+    action: InputAction,
+) -> impl FnMut(Res<KeyBindings>, Res<Input<KeyCode>>) -> bool + Clone {
+    let mut active = default;
+    move |bindings: Res<KeyBindings>, keys: Res<Input<KeyCode>>| {
+        active ^= bindings.just_pressed(action, &keys);
+        active
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::{
+    prelude::*,
+    core_pipeline::bloom::{BloomCompositeMode, BloomSettings},
+    window::{PresentMode, PrimaryWindow},
+};
+
+use crate::DisplayQuality;
+
+// This plugin applies the `DisplayQuality` setting to the cameras, the window and the
+// anti-aliasing whenever it changes, so the menu changes take effect right away. The breakout
+// game reads it on its own to decide how many particles to spawn.
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            apply_display_quality.run_if(resource_changed::<DisplayQuality>()),
+        );
+    }
+}
+
+// Everything a display quality changes lives here, so adding a new quality level or a new
+// knob is done in one place
+impl DisplayQuality {
+    // How strong the bloom glow is, or `None` to turn bloom off
+    pub fn bloom_intensity(&self) -> Option<f32> {
+        match self {
+            DisplayQuality::Low => None,
+            DisplayQuality::Medium => Some(0.5),
+            DisplayQuality::High => Some(0.6),
+        }
+    }
+
+    // WebGL2 only supports 1 or 4 samples, so that is all we use
+    pub fn msaa(&self) -> Msaa {
+        match self {
+            DisplayQuality::Low => Msaa::Off,
+            DisplayQuality::Medium | DisplayQuality::High => Msaa::Sample4,
+        }
+    }
+
+    // Low sticks to plain vsync, which every platform supports and keeps the frame rate down.
+    // High gives up vsync for the lowest latency where the platform allows it.
+    pub fn present_mode(&self) -> PresentMode {
+        match self {
+            DisplayQuality::Low => PresentMode::Fifo,
+            DisplayQuality::Medium => PresentMode::AutoVsync,
+            DisplayQuality::High => PresentMode::AutoNoVsync,
+        }
+    }
+
+    // How many particles fly off a block when it breaks
+    pub fn particles_per_block(&self) -> usize {
+        match self {
+            DisplayQuality::Low => 0,
+            DisplayQuality::Medium => 8,
+            DisplayQuality::High => 20,
+        }
+    }
+}
+
+fn bloom_settings(intensity: f32) -> BloomSettings {
+    BloomSettings {
+        intensity,
+        low_frequency_boost: 2.0,
+        low_frequency_boost_curvature: 0.3,
+        high_pass_frequency: 0.3,
+        composite_mode: BloomCompositeMode::Additive,
+        ..default()
+    }
+}
+
+fn apply_display_quality(
+    mut commands: Commands,
+    quality: Res<DisplayQuality>,
+    camera_query: Query<Entity, With<Camera>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    for camera in &camera_query {
+        match quality.bloom_intensity() {
+            Some(intensity) => commands.entity(camera).insert(bloom_settings(intensity)),
+            None => commands.entity(camera).remove::<BloomSettings>(),
+        };
+    }
+
+    commands.insert_resource(quality.msaa());
+
+    if let Ok(mut window) = window_query.get_single_mut() {
+        window.present_mode = quality.present_mode();
+    }
+}
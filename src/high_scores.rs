@@ -0,0 +1,99 @@
+use std::cmp::Reverse;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+// File the table is saved in, see `storage`
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+pub const MAX_HIGH_SCORES: usize = 10;
+
+// This plugin loads the high score table at startup. The menu shows it and adds to it when a
+// game ends with a good enough score.
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HighScores>()
+            .add_systems(Startup, load_high_scores);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HighScore {
+    pub name: String,
+    pub score: usize,
+    // The level the game ended on
+    pub level: usize,
+}
+
+// The best scores so far, best first, never more than `MAX_HIGH_SCORES` of them
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    // Whether a game that ended with `score` makes it into the table
+    pub fn qualifies(&self, score: usize) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_HIGH_SCORES
+                || self.entries.last().is_some_and(|lowest| score > lowest.score))
+    }
+
+    // Add a score in its place in the table, and return that place (starting at 0) if it made it
+    // in. Ties go below the scores that were there first.
+    pub fn insert(&mut self, high_score: HighScore) -> Option<usize> {
+        let position = self.entries
+            .iter()
+            .position(|entry| entry.score < high_score.score)
+            .unwrap_or(self.entries.len());
+        if position >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        self.entries.insert(position, high_score);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(position)
+    }
+
+    // Read the saved table. A missing table is just empty, and so is one that can't be read, so
+    // a broken file never keeps the game from starting.
+    pub fn load() -> HighScores {
+        let Some(contents) = storage::load(HIGH_SCORES_FILE) else {
+            return HighScores::default();
+        };
+
+        match ron::from_str::<HighScores>(&contents) {
+            Ok(mut high_scores) => {
+                high_scores.entries.sort_by_key(|entry| Reverse(entry.score));
+                high_scores.entries.truncate(MAX_HIGH_SCORES);
+                high_scores
+            }
+            Err(error) => {
+                warn!("Ignoring unreadable high scores: {error}");
+                HighScores::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => contents,
+            Err(error) => {
+                error!("Could not save high scores: {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = storage::save(HIGH_SCORES_FILE, &contents) {
+            error!("Could not save high scores: {error}");
+        }
+    }
+}
+
+fn load_high_scores(mut high_scores: ResMut<HighScores>) {
+    *high_scores = HighScores::load();
+}
@@ -0,0 +1,107 @@
+// Bevy systems take a lot of queries and resources as arguments, which these lints don't like
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+pub mod audio;
+pub mod splash;
+pub mod menu;
+pub mod breakout;
+pub mod controls;
+pub mod display;
+pub mod high_scores;
+pub mod settings;
+pub mod storage;
+
+use bevy::{
+    prelude::*,
+    core_pipeline::tonemapping::Tonemapping,
+};
+
+pub use crate::{
+    breakout::{
+        BreakoutPlugin, BreakoutSimPlugin, BreakoutRenderPlugin, BreakoutInputPlugin,
+        PausedState, Scoreboard, Level, Levels, LevelLayout, LevelSeed, GameMode,
+    },
+    splash::SplashPlugin,
+    display::DisplayPlugin,
+    audio::GameAudioPlugin,
+    menu::{MenuPlugin, MenuState},
+    high_scores::{HighScore, HighScores, HighScoresPlugin},
+    settings::{Settings, SettingsPlugin},
+    controls::{action_toggle_active, InputAction, KeyBindings},
+};
+use serde::{Deserialize, Serialize};
+
+pub const TEXT_COLOR: Color = Color::WHITE;
+
+// Enum that will be used as a global state for the game
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States, Reflect)]
+pub enum GameState {
+    #[default]
+    Splash,
+    Menu,
+    NewGame,
+    InGame,
+    LevelCleared,
+    GameOver,
+}
+
+// One of the settings that can be set through the menu. It will be a resource in the app
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum DisplayQuality {
+    Low,
+    Medium,
+    High,
+}
+
+// One of the settings that can be set through the menu. It will be a resource in the app
+#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Volume(pub u32);
+
+pub const MAX_VOLUME: u32 = 9;
+
+// Everything the game needs on top of bevy's `DefaultPlugins`: the game state, the splash
+// screen, the menus, the breakout game itself, the saved settings, the display quality, sound
+// and a camera.
+//
+// Apps that already have their own camera, or only want part of the game, can add the
+// individual plugins instead. `GameState` has to be declared with `add_state` in that case.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // Declare the game state, whose starting value is determined by the `Default` trait
+            .add_state::<GameState>()
+            .register_type::<GameState>()
+            .add_plugins((
+                SplashPlugin,
+                MenuPlugin,
+                BreakoutPlugin,
+                SettingsPlugin,
+                DisplayPlugin,
+                GameAudioPlugin,
+            ))
+            .add_systems(Startup, app_setup);
+    }
+}
+
+fn app_setup(
+    mut commands: Commands,
+) {
+    // Bloom is added by `DisplayPlugin`, depending on the display quality
+    commands.spawn(Camera2dBundle {
+        camera: Camera {
+            hdr: true, // Required for bloom
+            ..default()
+        },
+        tonemapping: Tonemapping::TonyMcMapface, // Use tonemapping that desaturates to white
+        ..default()
+    });
+}
+
+// Generic system that takes a component as a parameter, and will despawn all entities with that component
+pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
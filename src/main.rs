@@ -1,54 +1,20 @@
-mod splash;
-mod menu;
-mod breakout;
-
 use bevy::{
     prelude::*,
-    core_pipeline::{
-        bloom::{BloomCompositeMode, BloomSettings},
-        tonemapping::Tonemapping
-    },
-    input::common_conditions::input_toggle_active,
     window::{Window, WindowTheme, PresentMode},
-    //window::{Window, WindowMode, WindowTheme, Cursor, CursorGrabMode, PresentMode},
+    //window::{Window, WindowMode, WindowTheme, PresentMode},
 };
 use bevy_inspector_egui::quick::{
     WorldInspectorPlugin,
     StateInspectorPlugin,
 };
 
-use crate::{
-    breakout::BreakoutPlugin,
-    splash::SplashPlugin,
-    menu::MenuPlugin,
-};
-
-pub const TEXT_COLOR: Color = Color::WHITE;
-
-// Enum that will be used as a global state for the game
-#[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States, Reflect)]
-pub enum GameState {
-    #[default]
-    Splash,
-    Menu,
-    NewGame,
-    InGame,
-    GameOver,
-}
-
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-pub enum DisplayQuality {
-    Low,
-    Medium,
-    High,
-}
-
-// One of the two settings that can be set through the menu. It will be a resource in the app
-#[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy)]
-pub struct Volume(u32);
+use bevy_breakout::{action_toggle_active, GamePlugin, GameState, InputAction, LevelSeed};
 
 fn main() {
+    let level_seed = seed_arg()
+        .and_then(|seed| seed.parse().ok())
+        .map_or(LevelSeed::Random, LevelSeed::Fixed);
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -60,61 +26,37 @@ fn main() {
                 prevent_default_event_handling: false,
                 window_theme: Some(WindowTheme::Dark),
                 present_mode: PresentMode::AutoVsync,
-                /*
-                cursor: Cursor {
-                    grab_mode: CursorGrabMode::Confined,
-                    ..default()
-                },
-                */
+                // Grabbing the cursor is up to the mouse setting, see `MouseControl`
                 ..default()
             }),
             ..default()
         }))
-        // Declare the game state, whose starting value is determined by the `Default` trait
-        .add_state::<GameState>()
         .add_plugins((
-            SplashPlugin, MenuPlugin, BreakoutPlugin, 
+            GamePlugin,
             WorldInspectorPlugin::default().run_if(
-                input_toggle_active(false, KeyCode::Grave)
+                action_toggle_active(false, InputAction::ToggleInspector)
             ),
             StateInspectorPlugin::<GameState>::default().run_if(
-                input_toggle_active(false, KeyCode::Grave)
+                action_toggle_active(false, InputAction::ToggleInspector)
             ),
         ))
-        .insert_resource(DisplayQuality::Medium)
-        .insert_resource(Volume(7))
-        .add_systems(Startup, app_setup)
+        .insert_resource(level_seed)
         .run();
 }
 
-fn app_setup(
-    mut commands: Commands,
-) {
-    commands.spawn((
-        Camera2dBundle {
-            camera: Camera {
-                hdr: true, // Required for bloom
-                ..default()
-            },
-            tonemapping: Tonemapping::TonyMcMapface, // Use tonemapping that desaturates to white
-            ..default()
-        },
-        // Enable bloom for the camera
-        BloomSettings {
-            intensity: 0.5,
-            low_frequency_boost: 2.0,
-            low_frequency_boost_curvature: 0.3,
-            high_pass_frequency: 0.3,
-            composite_mode: BloomCompositeMode::Additive,
-            ..default()
-        }
-    ));
+// Pass `--seed <number>` to play the same generated levels as someone else
+#[cfg(not(target_arch = "wasm32"))]
+fn seed_arg() -> Option<String> {
+    std::env::args().skip_while(|arg| arg != "--seed").nth(1)
 }
 
-// Generic system that takes a component as a parameter, and will despawn all entities with that component
-fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
-    for entity in &to_despawn {
-        commands.entity(entity).despawn_recursive();
-    }
+// In the browser the seed goes in the page address instead, like `index.html?seed=<number>`
+#[cfg(target_arch = "wasm32")]
+fn seed_arg() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("seed="))
+        .map(String::from)
 }
-
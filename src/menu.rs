@@ -1,19 +1,44 @@
 use bevy::{
     app::AppExit,
     prelude::*,
-    input::common_conditions::input_toggle_active,
+    input::InputSystem,
+    window::ReceivedCharacter,
 };
 use bevy_inspector_egui::quick::StateInspectorPlugin;
 
-use super::{despawn_screen, DisplayQuality, GameState, Volume, TEXT_COLOR};
+mod focus;
+mod screen;
 
-use crate::breakout::PausedState;
+use focus::{
+    apply_menu_input, gamepad_menu_input, keyboard_menu_input, mouse_activation, ButtonActivated,
+    Focused, MenuInput,
+};
+use screen::{MenuButton, MenuScreen, MenuText};
+use super::{despawn_screen, DisplayQuality, GameState, Volume, MAX_VOLUME};
+
+use crate::{
+    breakout::{GameMode, Level, MouseControl, PausedState, Scoreboard},
+    controls::{action_toggle_active, InputAction, KeyBindings, INPUT_ACTIONS},
+    high_scores::{HighScore, HighScores, HighScoresPlugin},
+};
 
-// This plugin manages the menu, with 5 different screens:
-// - a main menu with "New Game", "Settings", "Quit"
-// - a settings menu with two submenus and a back button
-// - two settings screen with a setting that can be set and a back button
+// Longest name that can be entered for a high score
+const MAX_NAME_LENGTH: usize = 12;
+// Name used when nothing was entered
+const DEFAULT_NAME: &str = "Player";
+
+// This plugin manages the menu, with 11 different screens:
+// - a main menu with "New Game", "Settings", "High Scores", "Quit"
+// - a pause screen over the game with "Resume", "Restart", "Settings", "Quit to Menu"
+// - a screen asking to confirm anything that would throw away the game in progress
+// - a settings menu with four submenus and a back button
+// - three settings screen with a setting that can be set and a back button
+// - a controls screen where the key for every action can be changed
 // - a game over screen with an option to start a new game
+// - a screen to enter your name when the game ended with a high score
+// - the high score table
+// Every screen can be used with the mouse, the keyboard or a gamepad, see `focus`.
+// Screens are described with a `MenuScreen`, which builds their UI, see `screen`.
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
@@ -24,19 +49,30 @@ impl Plugin for MenuPlugin {
             // Current screen in the menu is handled by an independent state from `GameState`
             .add_state::<MenuState>()
             .register_type::<MenuState>()
+            .add_event::<ButtonActivated>()
+            .add_event::<MenuInput>()
+            .init_resource::<NameEntry>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<Rebinding>()
+            .init_resource::<Confirmation>()
             .add_plugins((
+                HighScoresPlugin,
                 StateInspectorPlugin::<MenuState>::default().run_if(
-                    input_toggle_active(false, KeyCode::Grave)
+                    action_toggle_active(false, InputAction::ToggleInspector)
                 ),
             ))
             .add_systems(OnEnter(GameState::Menu), menu_setup)
+            // When the game ends, show the game over screen
+            .add_systems(OnEnter(GameState::GameOver), game_over)
             // Systems to handle the main menu screen
             .add_systems(OnEnter(MenuState::Main), main_menu_setup)
-            .add_systems(
-                Update,
-                menu_update.run_if(in_state(MenuState::Main))
-            )
             .add_systems(OnExit(MenuState::Main), despawn_screen::<OnMainMenuScreen>)
+            // Systems to handle the pause screen
+            .add_systems(OnEnter(MenuState::Paused), pause_menu_setup)
+            .add_systems(OnExit(MenuState::Paused), despawn_screen::<OnPauseMenuScreen>)
+            // Systems to handle the confirm screen
+            .add_systems(OnEnter(MenuState::Confirm), confirm_menu_setup)
+            .add_systems(OnExit(MenuState::Confirm), despawn_screen::<OnConfirmMenuScreen>)
             // Systems to handle the settings menu screen
             .add_systems(OnEnter(MenuState::Settings), settings_menu_setup)
             .add_systems(
@@ -46,6 +82,24 @@ impl Plugin for MenuPlugin {
             // Systems to handle the game over screen
             .add_systems(OnEnter(MenuState::GameOver), game_over_menu_setup)
             .add_systems(OnExit(MenuState::GameOver), despawn_screen::<OnGameOverMenuScreen>)
+            // Systems to handle the high score name entry screen
+            .add_systems(OnEnter(MenuState::NewHighScore), new_high_score_menu_setup)
+            .add_systems(
+                Update,
+                (name_entry, update_name_entry_text)
+                    .chain()
+                    .run_if(in_state(MenuState::NewHighScore)),
+            )
+            .add_systems(
+                OnExit(MenuState::NewHighScore),
+                despawn_screen::<OnNewHighScoreMenuScreen>,
+            )
+            // Systems to handle the high score table screen
+            .add_systems(OnEnter(MenuState::HighScores), high_scores_menu_setup)
+            .add_systems(
+                OnExit(MenuState::HighScores),
+                despawn_screen::<OnHighScoresMenuScreen>,
+            )
             // Systems to handle the display settings screen
             .add_systems(
                 OnEnter(MenuState::SettingsDisplay),
@@ -59,6 +113,35 @@ impl Plugin for MenuPlugin {
                 OnExit(MenuState::SettingsDisplay),
                 despawn_screen::<OnDisplaySettingsMenuScreen>,
             )
+            // Systems to handle the game settings screen
+            .add_systems(OnEnter(MenuState::SettingsGame), game_settings_menu_setup)
+            .add_systems(
+                Update,
+                (setting_button::<GameMode>, setting_button::<MouseControl>)
+                    .run_if(in_state(MenuState::SettingsGame)),
+            )
+            .add_systems(
+                OnExit(MenuState::SettingsGame),
+                despawn_screen::<OnGameSettingsMenuScreen>,
+            )
+            // Systems to handle the controls settings screen
+            .add_systems(OnEnter(MenuState::SettingsControls), controls_settings_menu_setup)
+            .add_systems(
+                // Take the key before anything else sees it, so binding the pause key doesn't
+                // also unpause the game
+                PreUpdate,
+                rebind_key
+                    .after(InputSystem)
+                    .run_if(in_state(MenuState::SettingsControls)),
+            )
+            .add_systems(
+                Update,
+                update_controls_text.run_if(in_state(MenuState::SettingsControls)),
+            )
+            .add_systems(
+                OnExit(MenuState::SettingsControls),
+                despawn_screen::<OnControlsSettingsMenuScreen>,
+            )
             // Systems to handle the sound settings screen
             .add_systems(OnEnter(MenuState::SettingsSound), sound_settings_menu_setup)
             .add_systems(
@@ -69,10 +152,21 @@ impl Plugin for MenuPlugin {
                 OnExit(MenuState::SettingsSound),
                 despawn_screen::<OnSoundSettingsMenuScreen>,
             )
-            // Common systems to all screens that handles buttons behavior
+            // Common systems to all screens that handles buttons behavior. The pause screen and
+            // the settings opened from it are shown during `GameState::InGame`, so this goes by
+            // whether a screen is up rather than by the game state.
             .add_systems(
                 Update,
-                (menu_action, button_system).run_if(in_state(GameState::Menu))
+                (
+                    (
+                        mouse_activation,
+                        keyboard_menu_input,
+                        gamepad_menu_input,
+                        apply_menu_input,
+                        menu_action,
+                    ).chain(),
+                    button_system,
+                ).run_if(not(in_state(MenuState::Disabled)))
             );
     }
 }
@@ -81,21 +175,31 @@ impl Plugin for MenuPlugin {
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States, Reflect)]
 pub enum MenuState {
     Main,
+    Paused,
+    Confirm,
     Settings,
     SettingsDisplay,
     SettingsSound,
+    SettingsGame,
+    SettingsControls,
     #[default]
     Disabled,
     GameOver,
+    NewHighScore,
+    HighScores,
 }
 
 // Tag component used to tag entities added on the main menu screen
 #[derive(Component)]
 struct OnMainMenuScreen;
 
-// Tag component used to tag the "New Game" or "Resume" button for updating
+// Tag component used to tag entities added on the pause screen
+#[derive(Component)]
+struct OnPauseMenuScreen;
+
+// Tag component used to tag entities added on the confirm screen
 #[derive(Component)]
-struct NewOrResumeText;
+struct OnConfirmMenuScreen;
 
 // Tag component used to tag entities added on the settings menu screen
 #[derive(Component)]
@@ -109,44 +213,131 @@ struct OnDisplaySettingsMenuScreen;
 #[derive(Component)]
 struct OnSoundSettingsMenuScreen;
 
+// Tag component used to tag entities added on the game settings menu screen
+#[derive(Component)]
+struct OnGameSettingsMenuScreen;
+
+// Tag component used to tag entities added on the controls settings menu screen
+#[derive(Component)]
+struct OnControlsSettingsMenuScreen;
+
+// Tag component used to find the text showing the key for an action
+#[derive(Component)]
+struct BindingText(InputAction);
+
+// Tag component used to find the text saying how the last change of controls went
+#[derive(Component)]
+struct ControlsStatusText;
+
 // Tag component used to tag entities added on the game over menu screen
 #[derive(Component)]
 struct OnGameOverMenuScreen;
 
+// Tag component used to tag entities added on the high score name entry screen
+#[derive(Component)]
+struct OnNewHighScoreMenuScreen;
+
+// Tag component used to find the name being typed in
+#[derive(Component)]
+struct NameEntryText;
+
+// Tag component used to tag entities added on the high score table screen
+#[derive(Component)]
+struct OnHighScoresMenuScreen;
+
+// The name being typed in for a new high score
+#[derive(Resource, Default)]
+struct NameEntry(String);
+
+// An action waiting on the confirm screen, what the player is asked about it and the screen to
+// go back to if they change their mind
+#[derive(Resource, Default)]
+struct Confirmation {
+    action: Option<MenuButtonAction>,
+    question: &'static str,
+    previous: MenuState,
+}
+
+// The action waiting for a new key on the controls screen, and how the last change went
+#[derive(Resource, Default)]
+struct Rebinding {
+    action: Option<InputAction>,
+    message: String,
+}
+
 const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
 const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+// Dims the game behind the pause and confirm screens
+const PAUSE_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
 
 // Tag component used to mark which setting is currently selected
 #[derive(Component)]
 struct SelectedOption;
 
 // All actions that can be triggered from a button click
-#[derive(Component)]
+#[derive(Component, Clone, Copy)]
 enum MenuButtonAction {
     Play,
+    Resume,
+    // Like `Play`, but throws away the paused game, so it is confirmed first
+    NewGame,
+    Confirm,
+    Cancel,
     Settings,
     SettingsDisplay,
     SettingsSound,
+    SettingsGame,
+    SettingsControls,
+    Rebind(InputAction),
+    ResetControls,
+    HighScores,
+    SubmitHighScore,
     BackToMainMenu,
     BackToSettings,
+    QuitToMenu,
     Quit,
 }
 
-// This system handles changing all buttons color based on mouse interaction
+impl MenuButtonAction {
+    // Whether the button leaves the screen for the one it was opened from
+    fn is_back(&self) -> bool {
+        matches!(
+            self,
+            MenuButtonAction::Resume
+                | MenuButtonAction::Cancel
+                | MenuButtonAction::BackToMainMenu
+                | MenuButtonAction::BackToSettings
+        )
+    }
+
+    // What to ask before doing this, for the actions that throw away the game in progress
+    fn confirmation(&self) -> Option<&'static str> {
+        match self {
+            MenuButtonAction::NewGame => Some("Abandon this game and start a new one?"),
+            MenuButtonAction::QuitToMenu => Some("Abandon this game and quit to the menu?"),
+            _ => None,
+        }
+    }
+}
+
+// This system handles changing all buttons color based on mouse interaction and focus. A
+// focused button looks the same as a hovered one.
 fn button_system(
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, Option<&SelectedOption>),
-        (Changed<Interaction>, With<Button>),
+        (&Interaction, &mut BackgroundColor, Has<SelectedOption>, Has<Focused>),
+        With<Button>,
     >,
 ) {
-    for (interaction, mut color, selected) in &mut interaction_query {
-        *color = match (*interaction, selected) {
-            (Interaction::Pressed, _) | (Interaction::None, Some(_)) => PRESSED_BUTTON.into(),
-            (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
-            (Interaction::Hovered, None) => HOVERED_BUTTON.into(),
-            (Interaction::None, None) => NORMAL_BUTTON.into(),
+    for (interaction, mut color, selected, focused) in &mut interaction_query {
+        let hovered = *interaction == Interaction::Hovered || focused;
+        *color = match (*interaction, selected, hovered) {
+            (Interaction::Pressed, ..) => PRESSED_BUTTON.into(),
+            (_, true, true) => HOVERED_PRESSED_BUTTON.into(),
+            (_, true, false) => PRESSED_BUTTON.into(),
+            (_, false, true) => HOVERED_BUTTON.into(),
+            (_, false, false) => NORMAL_BUTTON.into(),
         }
     }
 }
@@ -154,17 +345,22 @@ fn button_system(
 // This system updates the settings when a new value for a setting is selected, and marks
 // the button as the one currently selected
 fn setting_button<T: Resource + Component + PartialEq + Copy>(
-    interaction_query: Query<(&Interaction, &T, Entity), (Changed<Interaction>, With<Button>)>,
-    mut selected_query: Query<(Entity, &mut BackgroundColor), With<SelectedOption>>,
+    mut activated: EventReader<ButtonActivated>,
+    button_query: Query<&T, With<Button>>,
+    // Only the buttons of this setting, as a screen can have rows for several
+    selected_query: Query<Entity, (With<SelectedOption>, With<T>)>,
     mut commands: Commands,
     mut setting: ResMut<T>,
 ) {
-    for (interaction, button_setting, entity) in &interaction_query {
-        if *interaction == Interaction::Pressed && *setting != *button_setting {
-            let (previous_button, mut previous_color) = selected_query.single_mut();
-            *previous_color = NORMAL_BUTTON.into();
-            commands.entity(previous_button).remove::<SelectedOption>();
-            commands.entity(entity).insert(SelectedOption);
+    for ButtonActivated(entity) in activated.read() {
+        let Ok(button_setting) = button_query.get(*entity) else {
+            continue;
+        };
+        if *setting != *button_setting {
+            for previous_button in &selected_query {
+                commands.entity(previous_button).remove::<SelectedOption>();
+            }
+            commands.entity(*entity).insert(SelectedOption);
             *setting = *button_setting;
         }
     }
@@ -180,536 +376,400 @@ fn menu_setup(
     }
 }
 
+// When the game ends, ask for a name if the score made it into the high scores, or just show
+// the game over screen
+fn game_over(
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    scoreboard: Res<Scoreboard>,
+    high_scores: Res<HighScores>,
+) {
+    game_state.set(GameState::Menu);
+    if high_scores.qualifies(scoreboard.score) {
+        menu_state.set(MenuState::NewHighScore);
+    } else {
+        menu_state.set(MenuState::GameOver);
+    }
+}
+
 fn main_menu_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
 ) {
-    // Common style for all buttons on the screen
-    let button_style = Style {
-        width: Val::Px(250.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
-    };
-    let button_icon_style = Style {
-        width: Val::Px(30.0),
-        // This takes the icons out of the flexbox flow, to be positioned exactly
-        position_type: PositionType::Absolute,
-        // The icon will be close to the left border of the button
-        left: Val::Px(10.0),
-        ..default()
-    };
-    let button_text_style = TextStyle {
-        font_size: 40.0,
-        color: TEXT_COLOR,
-        ..default()
-    };
+    // Display the game name, and a button for each action available from the main menu
+    MenuScreen::new()
+        .button_size(250.0, 65.0)
+        .title("Bevy Breakout")
+        .button(
+            MenuButton::action(MenuButtonAction::Play, "New Game")
+                .icon(asset_server.load("icons/right.png")),
+        )
+        .button(
+            MenuButton::action(MenuButtonAction::Settings, "Settings")
+                .icon(asset_server.load("icons/wrench.png")),
+        )
+        .button(MenuButton::action(MenuButtonAction::HighScores, "High Scores"))
+        .button(
+            MenuButton::action(MenuButtonAction::Quit, "Quit")
+                .icon(asset_server.load("icons/exitRight.png")),
+        )
+        .spawn(&mut commands, OnMainMenuScreen);
+}
 
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    ..default()
-                },
-                ..default()
-            },
-            OnMainMenuScreen,
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        flex_direction: FlexDirection::Column,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    background_color: Color::BLACK.into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    // Display the game name
-                    parent.spawn(
-                        TextBundle::from_section(
-                            "Bevy Breakout",
-                            TextStyle {
-                                font_size: 80.0,
-                                color: TEXT_COLOR,
-                                ..default()
-                            },
-                        )
-                        .with_style(Style {
-                            margin: UiRect::all(Val::Px(50.0)),
-                            ..default()
-                        }),
-                    );
-
-                    // Display three buttons for each action available from the main menu:
-                    // - new game
-                    // - settings
-                    // - quit
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            MenuButtonAction::Play,
-                        ))
-                        .with_children(|parent| {
-                            let icon = asset_server.load("icons/right.png");
-                            parent.spawn(ImageBundle {
-                                style: button_icon_style.clone(),
-                                image: UiImage::new(icon),
-                                ..default()
-                            });
-                            parent.spawn((TextBundle::from_section(
-                                    "New Game",
-                                    button_text_style.clone(),
-                                ),
-                                NewOrResumeText,
-                            ));
-                        });
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            MenuButtonAction::Settings,
-                        ))
-                        .with_children(|parent| {
-                            let icon = asset_server.load("icons/wrench.png");
-                            parent.spawn(ImageBundle {
-                                style: button_icon_style.clone(),
-                                image: UiImage::new(icon),
-                                ..default()
-                            });
-                            parent.spawn(TextBundle::from_section(
-                                "Settings",
-                                button_text_style.clone(),
-                            ));
-                        });
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style,
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            MenuButtonAction::Quit,
-                        ))
-                        .with_children(|parent| {
-                            let icon = asset_server.load("icons/exitRight.png");
-                            parent.spawn(ImageBundle {
-                                style: button_icon_style,
-                                image: UiImage::new(icon),
-                                ..default()
-                            });
-                            parent.spawn(TextBundle::from_section("Quit", button_text_style));
-                        });
-                });
-        });
+// Drawn over the game, which stays where it was behind it
+fn pause_menu_setup(mut commands: Commands) {
+    MenuScreen::new()
+        .background(PAUSE_BACKGROUND)
+        .button_size(250.0, 65.0)
+        .title("Paused")
+        .button(MenuButton::action(MenuButtonAction::Resume, "Resume"))
+        .button(MenuButton::action(MenuButtonAction::NewGame, "Restart"))
+        .button(MenuButton::action(MenuButtonAction::Settings, "Settings"))
+        .button(MenuButton::action(MenuButtonAction::QuitToMenu, "Quit to Menu"))
+        .spawn(&mut commands, OnPauseMenuScreen);
 }
 
-fn menu_update(
-    mut button_query: Query<&mut Text, With<NewOrResumeText>>,
-    paused_state: Res<State<PausedState>>,
-) {
-    for mut text in &mut button_query {
-        text.sections[0].value = match paused_state.get() {
-            PausedState::Paused => "Resume".into(),
-            _ => "New Game".into(),
-        }
-    }
+// Asks about the action in `Confirmation`. It can be opened from any screen, and goes back to
+// that screen if the answer is no, which is also what a stray Enter picks.
+fn confirm_menu_setup(mut commands: Commands, confirmation: Res<Confirmation>) {
+    MenuScreen::new()
+        .background(PAUSE_BACKGROUND)
+        .text(MenuText::new(confirmation.question).margin(50.0))
+        .row([
+            MenuButton::action(MenuButtonAction::Confirm, "Yes"),
+            MenuButton::action(MenuButtonAction::Cancel, "No").with(Focused),
+        ])
+        .spawn(&mut commands, OnConfirmMenuScreen);
 }
 
 fn settings_menu_setup(mut commands: Commands) {
-    let button_style = Style {
-        width: Val::Px(200.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
-    };
+    MenuScreen::new()
+        .button(MenuButton::action(MenuButtonAction::SettingsDisplay, "Display"))
+        .button(MenuButton::action(MenuButtonAction::SettingsSound, "Sound"))
+        .button(MenuButton::action(MenuButtonAction::SettingsGame, "Game"))
+        .button(MenuButton::action(MenuButtonAction::SettingsControls, "Controls"))
+        .button(MenuButton::action(MenuButtonAction::BackToMainMenu, "Back"))
+        .spawn(&mut commands, OnSettingsMenuScreen);
+}
 
-    let button_text_style = TextStyle {
-        font_size: 40.0,
-        color: TEXT_COLOR,
-        ..default()
-    };
+fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<DisplayQuality>) {
+    MenuScreen::new()
+        .labelled_row(
+            "Display Quality",
+            [DisplayQuality::Low, DisplayQuality::Medium, DisplayQuality::High]
+                .map(|quality| MenuButton::choice(quality, *display_quality).width(150.0)),
+        )
+        .button(MenuButton::action(MenuButtonAction::BackToSettings, "Back"))
+        .spawn(&mut commands, OnDisplaySettingsMenuScreen);
+}
 
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    ..default()
-                },
-                ..default()
-            },
-            OnSettingsMenuScreen,
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        flex_direction: FlexDirection::Column,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    background_color: Color::BLACK.into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    for (action, text) in [
-                        (MenuButtonAction::SettingsDisplay, "Display"),
-                        (MenuButtonAction::SettingsSound, "Sound"),
-                        (MenuButtonAction::BackToMainMenu, "Back"),
-                    ] {
-                        parent
-                            .spawn((
-                                ButtonBundle {
-                                    style: button_style.clone(),
-                                    background_color: NORMAL_BUTTON.into(),
-                                    ..default()
-                                },
-                                action,
-                            ))
-                            .with_children(|parent| {
-                                parent.spawn(TextBundle::from_section(
-                                    text,
-                                    button_text_style.clone(),
-                                ));
-                            });
-                    }
-                });
-        });
+fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
+    MenuScreen::new()
+        .labelled_row(
+            "Volume",
+            (0..=MAX_VOLUME).map(|level| {
+                MenuButton::new("")
+                    .width(30.0)
+                    .with(Volume(level))
+                    .selected(*volume == Volume(level))
+            }),
+        )
+        .button(MenuButton::action(MenuButtonAction::BackToSettings, "Back"))
+        .spawn(&mut commands, OnSoundSettingsMenuScreen);
 }
 
-fn display_settings_menu_setup(mut commands: Commands, display_quality: Res<DisplayQuality>) {
-    let button_style = Style {
-        width: Val::Px(200.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
-    };
-    let button_text_style = TextStyle {
-        font_size: 40.0,
-        color: TEXT_COLOR,
-        ..default()
+fn game_settings_menu_setup(
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    game_mode: Res<GameMode>,
+    mouse_control: Res<MouseControl>,
+) {
+    // Health mode is the original game, lives mode is classic breakout. The rules can't change
+    // halfway through a game, so when this is opened from the pause screen the mode is only shown.
+    let screen = if *game_state.get() == GameState::Menu {
+        MenuScreen::new().labelled_row(
+            "Mode",
+            [GameMode::Health, GameMode::Lives]
+                .map(|mode| MenuButton::choice(mode, *game_mode)),
+        )
+    } else {
+        MenuScreen::new().text(
+            MenuText::new(format!("Mode: {:?}, change it between games", *game_mode))
+                .font_size(30.0)
+                .margin(20.0),
+        )
     };
 
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    ..default()
-                },
-                ..default()
-            },
-            OnDisplaySettingsMenuScreen,
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        flex_direction: FlexDirection::Column,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    background_color: Color::BLACK.into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    // Create a new `NodeBundle`, this time not setting its `flex_direction`. It will
-                    // use the default value, `FlexDirection::Row`, from left to right.
-                    parent
-                        .spawn(NodeBundle {
-                            style: Style {
-                                align_items: AlignItems::Center,
-                                ..default()
-                            },
-                            background_color: Color::BLACK.into(),
-                            ..default()
-                        })
-                        .with_children(|parent| {
-                            // Display a label for the current setting
-                            parent.spawn(TextBundle::from_section(
-                                "Display Quality",
-                                button_text_style.clone(),
-                            ));
-                            // Display a button for each possible value
-                            for quality_setting in [
-                                DisplayQuality::Low,
-                                DisplayQuality::Medium,
-                                DisplayQuality::High,
-                            ] {
-                                let mut entity = parent.spawn((
-                                    ButtonBundle {
-                                        style: Style {
-                                            width: Val::Px(150.0),
-                                            height: Val::Px(65.0),
-                                            ..button_style.clone()
-                                        },
-                                        background_color: NORMAL_BUTTON.into(),
-                                        ..default()
-                                    },
-                                    quality_setting,
-                                ));
-                                entity.with_children(|parent| {
-                                    parent.spawn(TextBundle::from_section(
-                                        format!("{quality_setting:?}"),
-                                        button_text_style.clone(),
-                                    ));
-                                });
-                                if *display_quality == quality_setting {
-                                    entity.insert(SelectedOption);
-                                }
-                            }
-                        });
-                    // Display the back button to return to the settings screen
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style,
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            MenuButtonAction::BackToSettings,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Back", button_text_style));
-                        });
-                });
-        });
+    screen
+        .labelled_row(
+            "Mouse",
+            [MouseControl::Off, MouseControl::Follow, MouseControl::Confined]
+                .map(|mouse| MenuButton::choice(mouse, *mouse_control)),
+        )
+        .button(MenuButton::action(MenuButtonAction::BackToSettings, "Back"))
+        .spawn(&mut commands, OnGameSettingsMenuScreen);
 }
 
-fn sound_settings_menu_setup(mut commands: Commands, volume: Res<Volume>) {
-    let button_style = Style {
-        width: Val::Px(200.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
-    };
-    let button_text_style = TextStyle {
-        font_size: 40.0,
-        color: TEXT_COLOR,
-        ..default()
-    };
+fn controls_settings_menu_setup(mut commands: Commands, mut rebinding: ResMut<Rebinding>) {
+    // Start without a key being waited for, and without a message from last time
+    *rebinding = Rebinding::default();
+
+    // Smaller than on the other screens so every action fits
+    let mut screen = MenuScreen::new()
+        .button_size(200.0, 35.0)
+        .button_margin(2.0)
+        .font_size(25.0);
+
+    // One row per action, with a button showing its key. Pressing the button waits for the new
+    // key.
+    for action in INPUT_ACTIONS {
+        screen = screen.labelled_row(
+            MenuText::new(action.label()).width(250.0),
+            [MenuButton::action(MenuButtonAction::Rebind(action), "")
+                .text_with(BindingText(action))],
+        );
+    }
 
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    ..default()
-                },
-                ..default()
-            },
-            OnSoundSettingsMenuScreen,
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        flex_direction: FlexDirection::Column,
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    background_color: Color::BLACK.into(),
-                    ..default()
-                })
-                .with_children(|parent| {
-                    parent
-                        .spawn(NodeBundle {
-                            style: Style {
-                                align_items: AlignItems::Center,
-                                ..default()
-                            },
-                            background_color: Color::BLACK.into(),
-                            ..default()
-                        })
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Volume",
-                                button_text_style.clone(),
-                            ));
-                            for volume_setting in [0, 1, 2, 3, 4, 5, 6, 7, 8, 9] {
-                                let mut entity = parent.spawn((
-                                    ButtonBundle {
-                                        style: Style {
-                                            width: Val::Px(30.0),
-                                            height: Val::Px(65.0),
-                                            ..button_style.clone()
-                                        },
-                                        background_color: NORMAL_BUTTON.into(),
-                                        ..default()
-                                    },
-                                    Volume(volume_setting),
-                                ));
-                                if *volume == Volume(volume_setting) {
-                                    entity.insert(SelectedOption);
-                                }
-                            }
-                        });
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style,
-                                background_color: NORMAL_BUTTON.into(),
-                                ..default()
-                            },
-                            MenuButtonAction::BackToSettings,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section("Back", button_text_style));
-                        });
-                });
-        });
+    screen
+        .text(MenuText::new("").margin(10.0).with(ControlsStatusText))
+        .row([
+            MenuButton::action(MenuButtonAction::ResetControls, "Reset"),
+            MenuButton::action(MenuButtonAction::BackToSettings, "Back"),
+        ])
+        .spawn(&mut commands, OnControlsSettingsMenuScreen);
 }
 
-fn game_over_menu_setup(mut commands: Commands) {
-    let button_style = Style {
-        width: Val::Px(200.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
+// Give the action waiting for a key the next key pressed, unless another action already uses it
+fn rebind_key(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    let Some(action) = rebinding.action else {
+        return;
     };
+    let Some(key) = keys.get_just_pressed().next().copied() else {
+        return;
+    };
+    keys.clear_just_pressed(key);
 
-    let button_text_style = TextStyle {
-        font_size: 40.0,
-        color: TEXT_COLOR,
-        ..default()
+    rebinding.action = None;
+    rebinding.message = match key_bindings.rebind(action, key) {
+        Ok(()) => format!("{} is now {key:?}", action.label()),
+        Err(other) => format!("{key:?} is already used for {}", other.label()),
     };
+}
 
-    commands
-        .spawn((
-            NodeBundle {
-                style: Style {
-                    width: Val::Percent(100.0),
-                    height: Val::Percent(100.0),
-                    align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
-                    ..default()
-                },
-                ..default()
-            },
-            OnGameOverMenuScreen,
+fn update_controls_text(
+    key_bindings: Res<KeyBindings>,
+    rebinding: Res<Rebinding>,
+    mut binding_query: Query<(&mut Text, &BindingText), Without<ControlsStatusText>>,
+    mut status_query: Query<&mut Text, With<ControlsStatusText>>,
+) {
+    if !key_bindings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+
+    for (mut text, BindingText(action)) in &mut binding_query {
+        text.sections[0].value = if rebinding.action == Some(*action) {
+            String::from("Press a key")
+        } else {
+            format!("{:?}", key_bindings.key(*action))
+        };
+    }
+    for mut text in &mut status_query {
+        text.sections[0].value = rebinding.message.clone();
+    }
+}
+
+fn game_over_menu_setup(mut commands: Commands) {
+    MenuScreen::new()
+        .title("Game Over!")
+        .button(MenuButton::action(MenuButtonAction::Play, "New Game"))
+        .button(MenuButton::action(MenuButtonAction::Quit, "Quit"))
+        .spawn(&mut commands, OnGameOverMenuScreen);
+}
+
+fn new_high_score_menu_setup(
+    mut commands: Commands,
+    mut name_entry: ResMut<NameEntry>,
+    scoreboard: Res<Scoreboard>,
+    key_bindings: Res<KeyBindings>,
+) {
+    name_entry.0.clear();
+
+    MenuScreen::new()
+        .title("New High Score!")
+        .text(format!("Score: {}", scoreboard.score))
+        .text(format!(
+            "Type your name and press {:?}",
+            key_bindings.key(InputAction::ConfirmMenu),
         ))
-        .with_children(|parent| {
+        // The name as it is typed, with a cursor at the end
+        .custom(|parent, text_style| {
             parent
                 .spawn(NodeBundle {
                     style: Style {
-                        flex_direction: FlexDirection::Column,
+                        width: Val::Px(400.0),
+                        height: Val::Px(65.0),
+                        margin: UiRect::all(Val::Px(20.0)),
+                        justify_content: JustifyContent::Center,
                         align_items: AlignItems::Center,
                         ..default()
                     },
-                    background_color: Color::BLACK.into(),
+                    background_color: NORMAL_BUTTON.into(),
                     ..default()
                 })
                 .with_children(|parent| {
-                    // Display the game name
-                    parent.spawn(
-                        TextBundle::from_section(
-                            "Game Over!",
-                            TextStyle {
-                                font_size: 80.0,
-                                color: TEXT_COLOR,
-                                ..default()
-                            },
-                        )
-                        .with_style(Style {
-                            margin: UiRect::all(Val::Px(50.0)),
-                            ..default()
-                        }),
-                    );
-
-                    parent
-                        .spawn(NodeBundle {
-                            style: Style {
-                                flex_direction: FlexDirection::Column,
-                                align_items: AlignItems::Center,
-                                ..default()
-                            },
-                            background_color: Color::BLACK.into(),
-                            ..default()
-                        })
-                        .with_children(|parent| {
-                            for (action, text) in [
-                                (MenuButtonAction::Play, "New Game"),
-                                (MenuButtonAction::Quit, "Quit"),
-                            ] {
-                                parent
-                                    .spawn((
-                                        ButtonBundle {
-                                            style: button_style.clone(),
-                                            background_color: NORMAL_BUTTON.into(),
-                                            ..default()
-                                        },
-                                        action,
-                                    ))
-                                    .with_children(|parent| {
-                                        parent.spawn(TextBundle::from_section(
-                                            text,
-                                            button_text_style.clone(),
-                                        ));
-                                    });
-                            }
-                        });
+                    parent.spawn((
+                        TextBundle::from_section("_", text_style.clone()),
+                        NameEntryText,
+                    ));
                 });
-        });
+        })
+        // Focused from the start so Confirm submits the name
+        .button(MenuButton::action(MenuButtonAction::SubmitHighScore, "OK").with(Focused))
+        .spawn(&mut commands, OnNewHighScoreMenuScreen);
+}
+
+// Type in a name for the high score table. Confirm presses the "OK" button, which has the focus.
+fn name_entry(
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut name_entry: ResMut<NameEntry>,
+) {
+    for character in characters.read() {
+        let typed = character.char;
+        let allowed = typed.is_alphanumeric() || typed == ' ';
+        if allowed && name_entry.0.chars().count() < MAX_NAME_LENGTH {
+            name_entry.0.push(typed);
+        }
+    }
+
+    if bindings.just_pressed(InputAction::Erase, &keys) {
+        name_entry.0.pop();
+    }
+}
+
+fn update_name_entry_text(
+    name_entry: Res<NameEntry>,
+    mut query: Query<&mut Text, With<NameEntryText>>,
+) {
+    if !name_entry.is_changed() {
+        return;
+    }
+
+    for mut text in &mut query {
+        text.sections[0].value = format!("{}_", name_entry.0);
+    }
+}
+
+// Put the score from the game that just ended in the high score table and save it
+fn record_high_score(
+    name_entry: &NameEntry,
+    scoreboard: &Scoreboard,
+    level: &Level,
+    high_scores: &mut HighScores,
+) {
+    let name = name_entry.0.trim();
+    let name = if name.is_empty() { DEFAULT_NAME } else { name };
+
+    high_scores.insert(HighScore {
+        name: name.to_string(),
+        score: scoreboard.score,
+        level: level.0,
+    });
+    high_scores.save();
+}
+
+fn high_scores_menu_setup(mut commands: Commands, high_scores: Res<HighScores>) {
+    let mut screen = MenuScreen::new().text(MenuText::title("High Scores").margin(30.0));
+
+    if high_scores.entries.is_empty() {
+        screen = screen.text(MenuText::new("No high scores yet").font_size(30.0));
+    }
+    for (rank, entry) in high_scores.entries.iter().enumerate() {
+        screen = screen.text(
+            MenuText::new(format!(
+                "{}. {} - {} (level {})",
+                rank + 1, entry.name, entry.score, entry.level
+            ))
+            .font_size(30.0),
+        );
+    }
+
+    screen
+        .button(MenuButton::action(MenuButtonAction::BackToMainMenu, "Back"))
+        .spawn(&mut commands, OnHighScoresMenuScreen);
 }
 
 fn menu_action(
-    interaction_query: Query<
-        (&Interaction, &MenuButtonAction),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut activated: EventReader<ButtonActivated>,
+    button_query: Query<&MenuButtonAction, With<Button>>,
     mut app_exit_events: EventWriter<AppExit>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<GameState>>,
     paused_state: Res<State<PausedState>>,
     mut next_paused_state: ResMut<NextState<PausedState>>,
+    name_entry: Res<NameEntry>,
+    scoreboard: Res<Scoreboard>,
+    level: Res<Level>,
+    mut high_scores: ResMut<HighScores>,
+    mut key_bindings: ResMut<KeyBindings>,
+    mut rebinding: ResMut<Rebinding>,
+    current_menu_state: Res<State<MenuState>>,
+    mut confirmation: ResMut<Confirmation>,
 ) {
-    for (interaction, menu_button_action) in &interaction_query {
-        if *interaction == Interaction::Pressed {
-            match menu_button_action {
+    for ButtonActivated(entity) in activated.read() {
+        if let Ok(menu_button_action) = button_query.get(*entity) {
+            let menu_button_action = match *menu_button_action {
+                // Go ahead with the action that was waiting on the confirm screen
+                MenuButtonAction::Confirm => match confirmation.action.take() {
+                    Some(action) => action,
+                    None => continue,
+                },
+                action => match action.confirmation() {
+                    Some(question) => {
+                        *confirmation = Confirmation {
+                            action: Some(action),
+                            question,
+                            previous: *current_menu_state.get(),
+                        };
+                        menu_state.set(MenuState::Confirm);
+                        continue;
+                    }
+                    None => action,
+                },
+            };
+
+            match &menu_button_action {
                 MenuButtonAction::Quit => {
                     app_exit_events.send(AppExit);
                 }
-                MenuButtonAction::Play => {
+                // A new game clears whatever is left of the old one first, see
+                // `BreakoutSimPlugin`, and resets the score and level
+                MenuButtonAction::Play | MenuButtonAction::NewGame => {
                     next_paused_state.set(PausedState::Running);
-                    match paused_state.get() {
-                        PausedState::Nil => game_state.set(GameState::NewGame), //  Handle starting a new game
-                        _ => game_state.set(GameState::InGame),
-                    }
+                    game_state.set(GameState::NewGame);
                     menu_state.set(MenuState::Disabled);
                 }
+                MenuButtonAction::Resume => {
+                    next_paused_state.set(PausedState::Running);
+                    menu_state.set(MenuState::Disabled);
+                }
+                // Already swapped for the action being confirmed
+                MenuButtonAction::Confirm => {}
+                MenuButtonAction::Cancel => {
+                    confirmation.action = None;
+                    menu_state.set(confirmation.previous);
+                }
+                // Ending the run clears the playfield, see `BreakoutSimPlugin`
+                MenuButtonAction::QuitToMenu => {
+                    next_paused_state.set(PausedState::Nil);
+                    game_state.set(GameState::Menu);
+                    menu_state.set(MenuState::Main);
+                }
                 MenuButtonAction::Settings => menu_state.set(MenuState::Settings),
                 MenuButtonAction::SettingsDisplay => {
                     menu_state.set(MenuState::SettingsDisplay);
@@ -717,7 +777,32 @@ fn menu_action(
                 MenuButtonAction::SettingsSound => {
                     menu_state.set(MenuState::SettingsSound);
                 }
-                MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
+                MenuButtonAction::SettingsGame => {
+                    menu_state.set(MenuState::SettingsGame);
+                }
+                MenuButtonAction::SettingsControls => {
+                    menu_state.set(MenuState::SettingsControls);
+                }
+                MenuButtonAction::Rebind(action) => {
+                    rebinding.action = Some(*action);
+                }
+                MenuButtonAction::ResetControls => {
+                    *key_bindings = KeyBindings::default();
+                    *rebinding = Rebinding {
+                        action: None,
+                        message: String::from("Controls reset"),
+                    };
+                }
+                MenuButtonAction::HighScores => menu_state.set(MenuState::HighScores),
+                MenuButtonAction::SubmitHighScore => {
+                    record_high_score(&name_entry, &scoreboard, &level, &mut high_scores);
+                    menu_state.set(MenuState::HighScores);
+                }
+                // The settings can also be opened from the pause screen
+                MenuButtonAction::BackToMainMenu => match paused_state.get() {
+                    PausedState::Paused => menu_state.set(MenuState::Paused),
+                    _ => menu_state.set(MenuState::Main),
+                },
                 MenuButtonAction::BackToSettings => {
                     menu_state.set(MenuState::Settings);
                 }
@@ -0,0 +1,214 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{MenuButtonAction, SelectedOption};
+use crate::controls::{InputAction, KeyBindings};
+
+// How far a stick has to be pushed to move the focus
+const STICK_THRESHOLD: f32 = 0.5;
+
+// Buttons can be used without a mouse: the focus is moved between the buttons on screen with
+// the keyboard or a gamepad, and the focused one can be activated, just like clicking it. Every
+// button press, whichever way it came in, ends up as a `ButtonActivated` event for the menu
+// systems to act on.
+
+// Marks the button that has the focus. There is at most one, and none until the player starts
+// moving the focus around.
+#[derive(Component)]
+pub(super) struct Focused;
+
+// A button was clicked, or activated while it had the focus
+#[derive(Event)]
+pub(super) struct ButtonActivated(pub Entity);
+
+// What the player asked the menu to do, from whatever they are holding
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    // Through the buttons in reading order, like Tab and Shift+Tab
+    Next,
+    Previous,
+    Activate,
+    Back,
+}
+
+impl MenuInput {
+    // Which way the focus moves, in UI coordinates where y goes down
+    fn direction(&self) -> Option<Vec2> {
+        match self {
+            MenuInput::Up => Some(Vec2::NEG_Y),
+            MenuInput::Down => Some(Vec2::Y),
+            MenuInput::Left => Some(Vec2::NEG_X),
+            MenuInput::Right => Some(Vec2::X),
+            MenuInput::Next | MenuInput::Previous | MenuInput::Activate | MenuInput::Back => None,
+        }
+    }
+}
+
+// Clicking a button activates it
+pub(super) fn mouse_activation(
+    interaction_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<Button>)>,
+    mut activated: EventWriter<ButtonActivated>,
+) {
+    for (entity, interaction) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            activated.send(ButtonActivated(entity));
+        }
+    }
+}
+
+// The menu keys move the focus, with Shift going backwards through the buttons instead of
+// forwards. Confirm picks the focused button and Back goes back.
+pub(super) fn keyboard_menu_input(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut menu_input: EventWriter<MenuInput>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for (action, input) in [
+        (InputAction::MenuUp, MenuInput::Up),
+        (InputAction::MenuDown, MenuInput::Down),
+        (InputAction::MenuLeft, MenuInput::Left),
+        (InputAction::MenuRight, MenuInput::Right),
+        (InputAction::MenuNext, if shift { MenuInput::Previous } else { MenuInput::Next }),
+        (InputAction::ConfirmMenu, MenuInput::Activate),
+        (InputAction::MenuBack, MenuInput::Back),
+    ] {
+        if bindings.just_pressed(action, &keys) {
+            menu_input.send(input);
+        }
+    }
+}
+
+// The D-pad and left stick move the focus, South picks the focused button and East goes back.
+// Every connected gamepad works, so controllers can be plugged in at any time.
+pub(super) fn gamepad_menu_input(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut menu_input: EventWriter<MenuInput>,
+    // Where each stick was pointing last frame, so holding it only moves the focus once
+    mut stick_directions: Local<HashMap<Gamepad, Option<MenuInput>>>,
+) {
+    stick_directions.retain(|gamepad, _| gamepads.contains(*gamepad));
+
+    for gamepad in gamepads.iter() {
+        for (button, input) in [
+            (GamepadButtonType::DPadUp, MenuInput::Up),
+            (GamepadButtonType::DPadDown, MenuInput::Down),
+            (GamepadButtonType::DPadLeft, MenuInput::Left),
+            (GamepadButtonType::DPadRight, MenuInput::Right),
+            (GamepadButtonType::South, MenuInput::Activate),
+            (GamepadButtonType::East, MenuInput::Back),
+        ] {
+            if buttons.just_pressed(GamepadButton::new(gamepad, button)) {
+                menu_input.send(input);
+            }
+        }
+
+        let stick = Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0),
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.0),
+        );
+        let stick_direction = if stick.length() < STICK_THRESHOLD {
+            None
+        } else if stick.x.abs() > stick.y.abs() {
+            Some(if stick.x < 0.0 { MenuInput::Left } else { MenuInput::Right })
+        } else {
+            // Up on the stick is a positive y
+            Some(if stick.y > 0.0 { MenuInput::Up } else { MenuInput::Down })
+        };
+
+        let previous = stick_directions.insert(gamepad, stick_direction).flatten();
+        if let Some(input) = stick_direction.filter(|input| Some(*input) != previous) {
+            menu_input.send(input);
+        }
+    }
+}
+
+// Move the focus to the closest button in the direction asked for, activate the focused button,
+// or press the back button of the current screen
+pub(super) fn apply_menu_input(
+    mut commands: Commands,
+    mut menu_input: EventReader<MenuInput>,
+    button_query: Query<
+        (Entity, &GlobalTransform, Has<Focused>, Has<SelectedOption>, Option<&MenuButtonAction>),
+        With<Button>,
+    >,
+    mut activated: EventWriter<ButtonActivated>,
+) {
+    for input in menu_input.read() {
+        let focused = button_query
+            .iter()
+            .find(|(_, _, focused, ..)| *focused)
+            .map(|(entity, transform, ..)| (entity, transform.translation().truncate()));
+
+        match (input, focused) {
+            (MenuInput::Activate, Some((entity, _))) => activated.send(ButtonActivated(entity)),
+            (MenuInput::Activate, None) => {}
+            (MenuInput::Back, _) => {
+                if let Some((entity, ..)) = button_query
+                    .iter()
+                    .find(|(.., action)| action.is_some_and(MenuButtonAction::is_back))
+                {
+                    activated.send(ButtonActivated(entity));
+                }
+            }
+            // The first move puts the focus on the selected setting, or the first button
+            (_, None) => {
+                let first = button_query
+                    .iter()
+                    .min_by(|a, b| {
+                        let (a_position, b_position) = (a.1.translation(), b.1.translation());
+                        b.3.cmp(&a.3)
+                            .then(a_position.y.total_cmp(&b_position.y))
+                            .then(a_position.x.total_cmp(&b_position.x))
+                    });
+                if let Some((entity, ..)) = first {
+                    commands.entity(entity).insert(Focused);
+                }
+            }
+            (MenuInput::Next | MenuInput::Previous, Some((entity, _))) => {
+                let mut buttons: Vec<_> = button_query
+                    .iter()
+                    .map(|(other, transform, ..)| (other, transform.translation()))
+                    .collect();
+                buttons.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+                let Some(index) = buttons.iter().position(|(other, _)| *other == entity) else {
+                    continue;
+                };
+                // Going past the last button comes back around to the first
+                let other_index = if *input == MenuInput::Next {
+                    (index + 1) % buttons.len()
+                } else {
+                    (index + buttons.len() - 1) % buttons.len()
+                };
+                commands.entity(entity).remove::<Focused>();
+                commands.entity(buttons[other_index].0).insert(Focused);
+            }
+            (input, Some((entity, position))) => {
+                let Some(direction) = input.direction() else {
+                    continue;
+                };
+                let closest = button_query
+                    .iter()
+                    .filter_map(|(other, transform, ..)| {
+                        let offset = transform.translation().truncate() - position;
+                        let along = offset.dot(direction);
+                        // Buttons off to the side count as further away than ones straight ahead
+                        let across = offset.perp_dot(direction).abs();
+                        (along > 0.0).then_some((other, along + 2.0 * across))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+
+                if let Some((other, _)) = closest {
+                    commands.entity(entity).remove::<Focused>();
+                    commands.entity(other).insert(Focused);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use std::fmt::Debug;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+use super::{SelectedOption, NORMAL_BUTTON};
+use crate::TEXT_COLOR;
+
+// Every menu screen is laid out the same way: a black column in the middle of the screen with a
+// title, some text and buttons stacked on top of each other, and rows of buttons for settings.
+// `MenuScreen` describes what goes in the column, and `spawn` builds the UI for it:
+//
+//     MenuScreen::new()
+//         .title("Game Over!")
+//         .button(MenuButton::action(MenuButtonAction::Play, "New Game"))
+//         .button(MenuButton::action(MenuButtonAction::Quit, "Quit"))
+//         .spawn(&mut commands, OnGameOverMenuScreen);
+pub(super) struct MenuScreen {
+    items: Vec<MenuItem>,
+    background: Color,
+    button_width: f32,
+    button_height: f32,
+    button_margin: f32,
+    font_size: f32,
+}
+
+enum MenuItem {
+    Text(MenuText),
+    Button(MenuButton),
+    // Buttons side by side, after an optional label
+    Row(Option<MenuText>, Vec<MenuButton>),
+    // Anything the other items can't describe, built with the screen's text style
+    Custom(Box<dyn FnOnce(&mut ChildBuilder, &TextStyle)>),
+}
+
+// Components added to an entity once it is spawned
+type Insert = Box<dyn FnOnce(&mut EntityCommands)>;
+
+impl MenuScreen {
+    pub(super) fn new() -> MenuScreen {
+        MenuScreen {
+            items: Vec::new(),
+            background: Color::NONE,
+            button_width: 200.0,
+            button_height: 65.0,
+            button_margin: 20.0,
+            font_size: 40.0,
+        }
+    }
+
+    // Color of the whole screen around the column, see-through unless it is set
+    pub(super) fn background(mut self, color: Color) -> MenuScreen {
+        self.background = color;
+        self
+    }
+
+    // Size of the buttons that don't pick their own width
+    pub(super) fn button_size(mut self, width: f32, height: f32) -> MenuScreen {
+        self.button_width = width;
+        self.button_height = height;
+        self
+    }
+
+    pub(super) fn button_margin(mut self, margin: f32) -> MenuScreen {
+        self.button_margin = margin;
+        self
+    }
+
+    // Size of the text on the buttons and of any text that doesn't pick its own size
+    pub(super) fn font_size(mut self, font_size: f32) -> MenuScreen {
+        self.font_size = font_size;
+        self
+    }
+
+    pub(super) fn title(self, text: impl Into<String>) -> MenuScreen {
+        self.text(MenuText::title(text))
+    }
+
+    pub(super) fn text(mut self, text: impl Into<MenuText>) -> MenuScreen {
+        self.items.push(MenuItem::Text(text.into()));
+        self
+    }
+
+    pub(super) fn button(mut self, button: MenuButton) -> MenuScreen {
+        self.items.push(MenuItem::Button(button));
+        self
+    }
+
+    // A row of buttons
+    pub(super) fn row(mut self, buttons: impl IntoIterator<Item = MenuButton>) -> MenuScreen {
+        self.items.push(MenuItem::Row(None, buttons.into_iter().collect()));
+        self
+    }
+
+    // A row with a label in front of the buttons, like the choices for a setting
+    pub(super) fn labelled_row(
+        mut self,
+        label: impl Into<MenuText>,
+        buttons: impl IntoIterator<Item = MenuButton>,
+    ) -> MenuScreen {
+        self.items.push(MenuItem::Row(Some(label.into()), buttons.into_iter().collect()));
+        self
+    }
+
+    pub(super) fn custom(
+        mut self,
+        spawn: impl FnOnce(&mut ChildBuilder, &TextStyle) + 'static,
+    ) -> MenuScreen {
+        self.items.push(MenuItem::Custom(Box::new(spawn)));
+        self
+    }
+
+    // Build the screen, with `marker` on its root so it can be despawned with `despawn_screen`
+    pub(super) fn spawn(self, commands: &mut Commands, marker: impl Component) {
+        let button_style = Style {
+            width: Val::Px(self.button_width),
+            height: Val::Px(self.button_height),
+            margin: UiRect::all(Val::Px(self.button_margin)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        };
+        let text_style = TextStyle {
+            font_size: self.font_size,
+            color: TEXT_COLOR,
+            ..default()
+        };
+
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: self.background.into(),
+                    ..default()
+                },
+                marker,
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::BLACK.into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for item in self.items {
+                            match item {
+                                MenuItem::Text(text) => text.spawn(parent, &text_style),
+                                MenuItem::Button(button) => {
+                                    button.spawn(parent, &button_style, &text_style);
+                                }
+                                MenuItem::Row(label, buttons) => {
+                                    // A `NodeBundle` without a `flex_direction` uses the default,
+                                    // `FlexDirection::Row`, from left to right
+                                    parent
+                                        .spawn(NodeBundle {
+                                            style: Style {
+                                                align_items: AlignItems::Center,
+                                                ..default()
+                                            },
+                                            background_color: Color::BLACK.into(),
+                                            ..default()
+                                        })
+                                        .with_children(|parent| {
+                                            if let Some(label) = label {
+                                                label.spawn(parent, &text_style);
+                                            }
+                                            for button in buttons {
+                                                button.spawn(parent, &button_style, &text_style);
+                                            }
+                                        });
+                                }
+                                MenuItem::Custom(spawn) => spawn(parent, &text_style),
+                            }
+                        }
+                    });
+            });
+    }
+}
+
+// A line of text on a menu screen
+pub(super) struct MenuText {
+    text: String,
+    font_size: Option<f32>,
+    width: Option<f32>,
+    margin: f32,
+    inserts: Vec<Insert>,
+}
+
+impl MenuText {
+    pub(super) fn new(text: impl Into<String>) -> MenuText {
+        MenuText {
+            text: text.into(),
+            font_size: None,
+            width: None,
+            margin: 0.0,
+            inserts: Vec::new(),
+        }
+    }
+
+    // Big text with some room around it, for the top of a screen
+    pub(super) fn title(text: impl Into<String>) -> MenuText {
+        MenuText::new(text).font_size(80.0).margin(50.0)
+    }
+
+    pub(super) fn font_size(mut self, font_size: f32) -> MenuText {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    // A fixed width, so labels in different rows line up
+    pub(super) fn width(mut self, width: f32) -> MenuText {
+        self.width = Some(width);
+        self
+    }
+
+    pub(super) fn margin(mut self, margin: f32) -> MenuText {
+        self.margin = margin;
+        self
+    }
+
+    // Add components to the text, like a tag to find it again
+    pub(super) fn with(mut self, bundle: impl Bundle) -> MenuText {
+        self.inserts.push(Box::new(move |entity| {
+            entity.insert(bundle);
+        }));
+        self
+    }
+
+    fn spawn(self, parent: &mut ChildBuilder, text_style: &TextStyle) {
+        let mut entity = parent.spawn(
+            TextBundle::from_section(
+                self.text,
+                TextStyle {
+                    font_size: self.font_size.unwrap_or(text_style.font_size),
+                    ..text_style.clone()
+                },
+            )
+            .with_style(Style {
+                width: self.width.map_or(Val::Auto, Val::Px),
+                margin: UiRect::all(Val::Px(self.margin)),
+                ..default()
+            }),
+        );
+        for insert in self.inserts {
+            insert(&mut entity);
+        }
+    }
+}
+
+impl From<&str> for MenuText {
+    fn from(text: &str) -> MenuText {
+        MenuText::new(text)
+    }
+}
+
+impl From<String> for MenuText {
+    fn from(text: String) -> MenuText {
+        MenuText::new(text)
+    }
+}
+
+// A button on a menu screen. What it does is up to the components added with `with`, usually a
+// `MenuButtonAction` or the value of a setting.
+pub(super) struct MenuButton {
+    text: String,
+    icon: Option<Handle<Image>>,
+    width: Option<f32>,
+    selected: bool,
+    inserts: Vec<Insert>,
+    text_inserts: Vec<Insert>,
+}
+
+impl MenuButton {
+    pub(super) fn new(text: impl Into<String>) -> MenuButton {
+        MenuButton {
+            text: text.into(),
+            icon: None,
+            width: None,
+            selected: false,
+            inserts: Vec::new(),
+            text_inserts: Vec::new(),
+        }
+    }
+
+    pub(super) fn action(action: impl Component, text: impl Into<String>) -> MenuButton {
+        MenuButton::new(text).with(action)
+    }
+
+    // One of the values a setting can take, named after it and selected if it is `current`
+    pub(super) fn choice<T>(value: T, current: T) -> MenuButton
+    where
+        T: Component + PartialEq + Copy + Debug,
+    {
+        MenuButton::new(format!("{value:?}"))
+            .with(value)
+            .selected(value == current)
+    }
+
+    // An icon close to the left border of the button
+    pub(super) fn icon(mut self, icon: Handle<Image>) -> MenuButton {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub(super) fn width(mut self, width: f32) -> MenuButton {
+        self.width = Some(width);
+        self
+    }
+
+    // Mark the button as the current value of its setting
+    pub(super) fn selected(mut self, selected: bool) -> MenuButton {
+        self.selected = selected;
+        self
+    }
+
+    // Add components to the button
+    pub(super) fn with(mut self, bundle: impl Bundle) -> MenuButton {
+        self.inserts.push(Box::new(move |entity| {
+            entity.insert(bundle);
+        }));
+        self
+    }
+
+    // Add components to the text on the button, like a tag to change it later
+    pub(super) fn text_with(mut self, bundle: impl Bundle) -> MenuButton {
+        self.text_inserts.push(Box::new(move |entity| {
+            entity.insert(bundle);
+        }));
+        self
+    }
+
+    fn spawn(self, parent: &mut ChildBuilder, button_style: &Style, text_style: &TextStyle) {
+        let mut entity = parent.spawn(ButtonBundle {
+            style: Style {
+                width: self.width.map_or(button_style.width, Val::Px),
+                ..button_style.clone()
+            },
+            background_color: NORMAL_BUTTON.into(),
+            ..default()
+        });
+        for insert in self.inserts {
+            insert(&mut entity);
+        }
+        if self.selected {
+            entity.insert(SelectedOption);
+        }
+
+        let text_inserts = self.text_inserts;
+        entity.with_children(|parent| {
+            if let Some(icon) = self.icon {
+                parent.spawn(ImageBundle {
+                    style: Style {
+                        width: Val::Px(30.0),
+                        // This takes the icons out of the flexbox flow, to be positioned exactly
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(10.0),
+                        ..default()
+                    },
+                    image: UiImage::new(icon),
+                    ..default()
+                });
+            }
+            // Buttons like the volume levels have nothing written on them
+            if !self.text.is_empty() || !text_inserts.is_empty() {
+                let mut text =
+                    parent.spawn(TextBundle::from_section(self.text, text_style.clone()));
+                for insert in text_inserts {
+                    insert(&mut text);
+                }
+            }
+        });
+    }
+}
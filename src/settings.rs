@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    breakout::{GameMode, MouseControl},
+    controls::{InputAction, KeyBindings},
+    storage, DisplayQuality, Volume, MAX_VOLUME,
+};
+
+// File the settings are saved in, see `storage`
+const SETTINGS_FILE: &str = "settings.toml";
+
+// This plugin loads the saved settings before the menu is shown and saves them again whenever
+// one of them changes. Settings that are missing from the file keep their defaults, and ones
+// that can't be understood are skipped with a warning.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = Settings::default();
+        app
+            .insert_resource(settings.display_quality)
+            .insert_resource(settings.volume)
+            .insert_resource(settings.key_bindings.clone())
+            .insert_resource(SavedSettings(settings))
+            .add_systems(PreStartup, load_settings)
+            .add_systems(
+                Update,
+                save_settings.run_if(
+                    resource_changed::<DisplayQuality>()
+                        .or_else(resource_changed::<Volume>())
+                        .or_else(resource_changed::<GameMode>())
+                        .or_else(resource_changed::<MouseControl>())
+                        .or_else(resource_changed::<KeyBindings>()),
+                ),
+            );
+    }
+}
+
+// Everything that is kept between runs, as it is written to the settings file
+#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct Settings {
+    pub display_quality: DisplayQuality,
+    pub volume: Volume,
+    pub game_mode: GameMode,
+    pub mouse_control: MouseControl,
+    pub key_bindings: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            display_quality: DisplayQuality::Medium,
+            volume: Volume(7),
+            game_mode: GameMode::default(),
+            mouse_control: MouseControl::default(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // Read the saved settings, one at a time so that a bad value only loses that setting
+    pub fn load() -> Settings {
+        let mut settings = Settings::default();
+        let Some(contents) = storage::load(SETTINGS_FILE) else {
+            return settings;
+        };
+
+        let table = match contents.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(error) => {
+                warn!("Ignoring unreadable settings: {error}");
+                return settings;
+            }
+        };
+
+        for (key, value) in table {
+            let valid = match key.as_str() {
+                "display_quality" => value
+                    .clone()
+                    .try_into()
+                    .map(|display_quality| settings.display_quality = display_quality)
+                    .is_ok(),
+                "volume" => match value.clone().try_into() {
+                    Ok(Volume(volume)) if volume <= MAX_VOLUME => {
+                        settings.volume = Volume(volume);
+                        true
+                    }
+                    _ => false,
+                },
+                "game_mode" => value
+                    .clone()
+                    .try_into()
+                    .map(|game_mode| settings.game_mode = game_mode)
+                    .is_ok(),
+                "mouse_control" => value
+                    .clone()
+                    .try_into()
+                    .map(|mouse_control| settings.mouse_control = mouse_control)
+                    .is_ok(),
+                "key_bindings" => match value.as_table() {
+                    Some(bindings) => {
+                        settings.key_bindings = load_key_bindings(bindings);
+                        true
+                    }
+                    None => false,
+                },
+                _ => {
+                    warn!("Ignoring unknown setting `{key}`");
+                    continue;
+                }
+            };
+
+            if !valid {
+                warn!("Ignoring invalid value for setting `{key}`: {value}");
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self) {
+        let contents = match toml::to_string(self) {
+            Ok(contents) => contents,
+            Err(error) => {
+                error!("Could not save settings: {error}");
+                return;
+            }
+        };
+
+        if let Err(error) = storage::save(SETTINGS_FILE, &contents) {
+            error!("Could not save settings: {error}");
+        }
+    }
+}
+
+// Bindings are read one at a time like the other settings. If the ones that could be read
+// clash with each other, none of them can be trusted and the defaults are used instead.
+fn load_key_bindings(table: &toml::Table) -> KeyBindings {
+    let mut bindings = Vec::new();
+    for (action, key) in table {
+        let Ok(action) = toml::Value::String(action.clone()).try_into::<InputAction>() else {
+            warn!("Ignoring key binding for unknown action `{action}`");
+            continue;
+        };
+        match key.clone().try_into::<KeyCode>() {
+            Ok(key) => bindings.push((action, key)),
+            Err(_) => warn!("Ignoring invalid key for `{action:?}`: {key}"),
+        }
+    }
+
+    let mut key_bindings = KeyBindings::default();
+    key_bindings.extend(bindings);
+    if key_bindings.has_conflicts() {
+        warn!("Ignoring key bindings that use the same key twice");
+        return KeyBindings::default();
+    }
+    key_bindings
+}
+
+// The settings as they were last loaded or saved, so they are only written when they change
+#[derive(Resource)]
+struct SavedSettings(Settings);
+
+fn load_settings(mut commands: Commands, mut saved: ResMut<SavedSettings>) {
+    let settings = Settings::load();
+    commands.insert_resource(settings.display_quality);
+    commands.insert_resource(settings.volume);
+    commands.insert_resource(settings.game_mode);
+    commands.insert_resource(settings.mouse_control);
+    commands.insert_resource(settings.key_bindings.clone());
+    saved.0 = settings;
+}
+
+fn save_settings(
+    display_quality: Res<DisplayQuality>,
+    volume: Res<Volume>,
+    game_mode: Res<GameMode>,
+    mouse_control: Res<MouseControl>,
+    key_bindings: Res<KeyBindings>,
+    mut saved: ResMut<SavedSettings>,
+) {
+    let settings = Settings {
+        display_quality: *display_quality,
+        volume: *volume,
+        game_mode: *game_mode,
+        mouse_control: *mouse_control,
+        key_bindings: key_bindings.clone(),
+    };
+    if settings != saved.0 {
+        settings.save();
+        saved.0 = settings;
+    }
+}
@@ -0,0 +1,73 @@
+// Small text files that have to survive between runs, like the high scores and settings. On
+// desktop they are files in the game's folder in the user's data directory, in the browser they
+// are entries in `localStorage`.
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("could not find a directory to save to")]
+    NoDirectory,
+    #[error("could not write save file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("browser storage is not available")]
+    NoBrowserStorage,
+    #[error("could not write to browser storage")]
+    Browser,
+}
+
+// Get what was last saved in the file called `name`, if anything
+pub fn load(name: &str) -> Option<String> {
+    platform::load(name)
+}
+
+pub fn save(name: &str, contents: &str) -> Result<(), StorageError> {
+    platform::save(name, contents)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use std::{fs, path::PathBuf};
+
+    use super::StorageError;
+
+    const GAME_DIRECTORY: &str = "bevy-breakout";
+
+    fn path(name: &str) -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(GAME_DIRECTORY).join(name))
+    }
+
+    pub fn load(name: &str) -> Option<String> {
+        fs::read_to_string(path(name)?).ok()
+    }
+
+    pub fn save(name: &str, contents: &str) -> Result<(), StorageError> {
+        let path = path(name).ok_or(StorageError::NoDirectory)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    use super::StorageError;
+
+    const KEY_PREFIX: &str = "bevy-breakout.";
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    pub fn load(name: &str) -> Option<String> {
+        local_storage()?.get_item(&format!("{KEY_PREFIX}{name}")).ok()?
+    }
+
+    pub fn save(name: &str, contents: &str) -> Result<(), StorageError> {
+        local_storage()
+            .ok_or(StorageError::NoBrowserStorage)?
+            .set_item(&format!("{KEY_PREFIX}{name}"), contents)
+            .map_err(|_| StorageError::Browser)
+    }
+}
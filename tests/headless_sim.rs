@@ -0,0 +1,91 @@
+// The simulation is meant to run without a window, for CI and bots. This builds it the way they
+// would, with nothing but `MinimalPlugins`, so any system that needs a render, UI or input
+// resource makes the app panic here.
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_breakout::{
+    breakout::{Ball, Block, BlockKind, BlockSpec, Docked, Paddle, Velocity, PADDLE_SIZE},
+    BreakoutSimPlugin, GameState, LevelLayout, LevelSeed, Levels, PausedState, Scoreboard,
+};
+
+// One frame per fixed tick, so every update runs exactly one tick of the simulation
+const TICK: Duration = Duration::from_micros(15_625);
+
+// Two blocks: one straight above the paddle and one well off to the side
+const TARGET: Vec2 = Vec2::new(0.0, 100.0);
+const BYSTANDER: Vec2 = Vec2::new(300.0, 200.0);
+
+fn headless_app() -> App {
+    let block = |position| BlockSpec {
+        position,
+        color: Color::WHITE,
+        kind: BlockKind::default(),
+    };
+
+    let mut app = App::new();
+    app
+        .add_plugins(MinimalPlugins)
+        .add_state::<GameState>()
+        .add_plugins(BreakoutSimPlugin)
+        .insert_resource(LevelSeed::Fixed(1))
+        .insert_resource(Levels {
+            layouts: vec![LevelLayout {
+                name: String::from("Test"),
+                blocks: vec![block(TARGET), block(BYSTANDER)],
+                paddle_width: PADDLE_SIZE.x,
+            }],
+            seed: 1,
+        })
+        .insert_resource(TimeUpdateStrategy::ManualDuration(TICK));
+
+    app.world.resource_mut::<NextState<GameState>>().set(GameState::NewGame);
+    app.world.resource_mut::<NextState<PausedState>>().set(PausedState::Running);
+    app
+}
+
+fn block_at(app: &mut App, position: Vec2) -> Option<(Entity, BlockKind)> {
+    app.world
+        .query::<(Entity, &Transform, &Block)>()
+        .iter(&app.world)
+        .find(|(_, transform, _)| transform.translation.truncate().distance(position) < 0.01)
+        .map(|(entity, _, block)| (entity, block.kind))
+}
+
+#[test]
+fn ball_breaks_block_above_paddle() {
+    let mut app = headless_app();
+
+    // Set up the game
+    app.update();
+    app.update();
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::InGame);
+    assert_eq!(app.world.query::<&Paddle>().iter(&app.world).count(), 1);
+    let (target, kind) = block_at(&mut app, TARGET).expect("no block above the paddle");
+    assert!(block_at(&mut app, BYSTANDER).is_some());
+
+    // Launch the ball straight up at the block
+    let ball = app.world.query_filtered::<Entity, With<Ball>>().single(&app.world);
+    let start = app.world.get::<Transform>(ball).unwrap().translation;
+    assert_eq!(start.x, TARGET.x);
+    let speed = app.world.get::<Velocity>(ball).unwrap().length();
+    app.world.entity_mut(ball).remove::<Docked>();
+    app.world.get_mut::<Velocity>(ball).unwrap().0 = Vec2::Y * speed;
+
+    for _ in 0..200 {
+        app.update();
+        if app.world.get_entity(target).is_none() {
+            break;
+        }
+    }
+
+    assert!(app.world.get_entity(target).is_none(), "the block above the paddle wasn't broken");
+    assert_eq!(app.world.resource::<Scoreboard>().score, kind.score());
+    // Only the block that was hit broke, so the level goes on
+    assert!(block_at(&mut app, BYSTANDER).is_some());
+    assert_eq!(*app.world.resource::<State<GameState>>().get(), GameState::InGame);
+
+    // And the ball is on its way back down
+    assert!(app.world.get::<Velocity>(ball).unwrap().y < 0.0);
+    assert!(app.world.get::<Transform>(ball).unwrap().translation.y < TARGET.y);
+}